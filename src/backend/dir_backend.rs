@@ -0,0 +1,335 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Error, Read};
+use std::path::{Path, PathBuf};
+
+use crate::backend::{
+    ArchiveReader, ArchiveWriter, EntryCallback, EntryInfo, FileEncoder
+};
+use crate::model::archive_entry::EntryKind;
+use crate::model::compression::Compression;
+use crate::model::file_metadata::FileMetadata;
+
+
+/// Writes the entries uncompressed into a plain directory (e.g. a mounted
+/// network share or an external drive). Existing files are overwritten,
+/// entries below the path of a copy task which it no longer contains are
+/// removed, and files which are not part of the copy tasks are kept.
+pub struct DirArchiveWriter {
+    /// The root directory of the mirror.
+    root: PathBuf,
+
+    /// The directories and their metadata, applied after their content was
+    /// written.
+    directories: Vec<(PathBuf, FileMetadata)>,
+
+    /// The paths of all entries written by this run.
+    written: HashSet<PathBuf>,
+
+    /// The paths of the copy tasks whose stale entries are removed, with the
+    /// paths of the other copy tasks, which are kept.
+    prune_roots: Vec<(PathBuf, Vec<String>)>
+}
+
+impl DirArchiveWriter {
+    /// Creates the root directory of the mirror if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the root directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DirArchiveWriter)` - The writer.
+    /// * `Err(Error)` - If the directory cannot be created.
+    pub fn create(path: &str) -> Result<Self, Error> {
+        fs::create_dir_all(path)?;
+        Ok(Self {
+            root: PathBuf::from(path),
+            directories: Vec::new(),
+            written: HashSet::new(),
+            prune_roots: Vec::new()
+        })
+    }
+
+    /// Returns the path of an entry within the mirror and removes an existing
+    /// file or symlink at this path.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The path of the entry.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PathBuf)` - The path of the entry within the mirror.
+    /// * `Err(Error)` - If the existing file cannot be removed.
+    fn prepare_path(&mut self, name: &str) -> Result<PathBuf, Error> {
+        let path = self.root.join(name);
+        self.written.insert(path.clone());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Files of a previous run might be read-only
+        if path.is_symlink() || path.is_file() {
+            fs::remove_file(&path)?;
+        }
+
+        Ok(path)
+    }
+
+    /// Removes all entries below a directory which weren't written by this
+    /// run (e.g. files which were deleted from the source). Entries of other
+    /// copy tasks are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory within the mirror.
+    /// * `kept` - The paths of the other copy tasks.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the stale entries were removed.
+    /// * `Err(Error)` - If an entry cannot be removed.
+    fn prune(&self, directory: &Path, kept: &[String]) -> Result<(), Error> {
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let is_dir = entry.file_type()?.is_dir();
+
+            // The path of the entry within the mirror
+            let name = path.strip_prefix(&self.root).unwrap_or(&path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let of_other_task = kept.iter().any(|kept| {
+                kept.is_empty() || name == *kept
+                    || name.starts_with(&format!("{}/", kept))
+            });
+            let parent_of_other_task = kept.iter().any(|kept| {
+                kept.starts_with(&format!("{}/", name))
+            });
+
+            if of_other_task {
+                continue;
+            }
+            if self.written.contains(&path) || parent_of_other_task {
+                if is_dir {
+                    self.prune(&path, kept)?;
+                }
+                continue;
+            }
+
+            println!("Removing: {}", path.display());
+            if is_dir {
+                FileMetadata::make_tree_writable(&path)?;
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ArchiveWriter for DirArchiveWriter {
+    fn file_encoder(&self, _compression: Compression) -> FileEncoder {
+        // Files are written as they are
+        Box::new(|_name, content, _metadata| Ok(content))
+    }
+
+    fn write_file(&mut self, name: &str, data: Vec<u8>, metadata: FileMetadata)
+    -> io::Result<()> {
+        let path = self.prepare_path(name)?;
+        fs::write(&path, data)?;
+        metadata.apply(&path)
+    }
+
+    fn write_stream(
+        &mut self, name: &str, content: &mut dyn Read, _size: u64,
+        _compression: Compression, metadata: FileMetadata
+    ) -> io::Result<()> {
+        let path = self.prepare_path(name)?;
+        io::copy(content, &mut File::create(&path)?)?;
+        metadata.apply(&path)
+    }
+
+    fn write_directory(&mut self, name: &str, metadata: FileMetadata)
+    -> io::Result<()> {
+        let path = self.root.join(name);
+        if path.is_symlink() {
+            fs::remove_file(&path)?;
+        }
+        // A previous run might have made it read-only, its mode is applied
+        // again in `finalize`
+        fs::create_dir_all(&path)?;
+        FileMetadata::make_writable(&path)?;
+        self.written.insert(path.clone());
+        self.directories.push((path, metadata));
+        Ok(())
+    }
+
+    fn write_symlink(&mut self, name: &str, target: &Path) -> io::Result<()> {
+        let path = self.prepare_path(name)?;
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target, &path)?;
+        #[cfg(not(unix))]
+        let _ = (path, target);
+
+        Ok(())
+    }
+
+    fn copy_unchanged(&mut self, name: &str) -> io::Result<bool> {
+        // The file of the previous update is still in the mirror
+        let path = self.root.join(name);
+        let unchanged = path.is_file() && !path.is_symlink();
+        if unchanged {
+            self.written.insert(path);
+        }
+        Ok(unchanged)
+    }
+
+    fn prune_below(&mut self, name: &str, kept: &[String]) {
+        self.prune_roots.push((self.root.join(name), kept.to_vec()));
+    }
+
+    fn finalize(mut self: Box<Self>) -> io::Result<()> {
+        // Only the content of directories which were written is pruned
+        for (root, kept) in &self.prune_roots {
+            if self.written.contains(root) && root.is_dir()
+            && !root.is_symlink() {
+                self.prune(root, kept)?;
+            }
+        }
+
+        // Writing the content changes the modification time of the
+        // directories (deepest directories first)
+        self.directories.sort_by(|a, b| b.0.cmp(&a.0));
+        for (path, metadata) in &self.directories {
+            metadata.apply(path)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Reads the entries of a plain directory in the same way as the entries of
+/// an archive.
+pub struct DirArchiveReader {
+    /// The root directory of the mirror.
+    root: PathBuf
+}
+
+impl DirArchiveReader {
+    /// Opens a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the root directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DirArchiveReader)` - The reader.
+    /// * `Err(Error)` - If the path isn't a directory.
+    pub fn open(path: &str) -> Result<Self, Error> {
+        if !Path::new(path).is_dir() {
+            return Err(Error::new(
+                io::ErrorKind::NotFound,
+                format!("Directory '{}' not found", path)
+            ));
+        }
+        Ok(Self { root: PathBuf::from(path) })
+    }
+
+    /// Recursively collects the entries of a directory. Entries are sorted by
+    /// name and symbolic links are not followed.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir_path` - The path of the directory being processed.
+    /// * `entries` - The collected entries together with their path on disk.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the directory was successfully read.
+    /// * `Err(Error)` - If an error occurs.
+    fn collect_entries(
+        &self,
+        dir_path: &Path,
+        entries: &mut Vec<(EntryInfo, PathBuf)>
+    ) -> Result<(), Error> {
+        let mut dir_entries = fs::read_dir(dir_path)?
+            .collect::<Result<Vec<_>, Error>>()?;
+        dir_entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in dir_entries {
+            let path = entry.path();
+            let name = path.strip_prefix(&self.root).unwrap_or(&path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let file_type = entry.file_type()?;
+            let (kind, size) = if file_type.is_symlink() {
+                let target = fs::read_link(&path)?;
+                let size = target.as_os_str().len() as u64;
+                (EntryKind::Symlink(target), size)
+            } else if file_type.is_dir() {
+                (EntryKind::Directory, 0)
+            } else if file_type.is_file() {
+                (EntryKind::File, entry.metadata()?.len())
+            } else {
+                continue;
+            };
+
+            let metadata = if file_type.is_symlink() {
+                FileMetadata::default()
+            } else {
+                FileMetadata::from_path(&path)?
+            };
+            let is_dir = kind == EntryKind::Directory;
+            entries.push((
+                EntryInfo { name, kind, size, crc32: None, metadata },
+                path.clone()
+            ));
+
+            if is_dir {
+                self.collect_entries(&path, entries)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ArchiveReader for DirArchiveReader {
+    fn list_entries(&mut self) -> io::Result<Vec<EntryInfo>> {
+        let mut entries = Vec::new();
+        self.collect_entries(&self.root.clone(), &mut entries)?;
+        Ok(entries.into_iter().map(|(info, _path)| info).collect())
+    }
+
+    fn read_entries(&mut self, callback: &mut EntryCallback)
+    -> io::Result<()> {
+        let mut entries = Vec::new();
+        self.collect_entries(&self.root.clone(), &mut entries)?;
+
+        for (info, path) in entries {
+            match &info.kind {
+                // Like in ZIP archives, the content of a symlink entry is
+                // the target path
+                EntryKind::Symlink(target) => {
+                    let target = target.to_string_lossy().to_string();
+                    callback(&info, &mut target.as_bytes())?;
+                },
+                EntryKind::Directory => callback(&info, &mut io::empty())?,
+                EntryKind::File => callback(&info, &mut File::open(&path)?)?
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+pub mod dir_backend;
+pub mod tar_backend;
+pub mod zip_backend;
+
+use std::io::{self, Error, ErrorKind, Read};
+use std::path::{Component, Path};
+
+use crate::model::archive_entry::EntryKind;
+use crate::model::compression::Compression;
+use crate::model::config::Config;
+use crate::model::file_metadata::FileMetadata;
+use dir_backend::{DirArchiveReader, DirArchiveWriter};
+use tar_backend::{TarArchiveReader, TarArchiveWriter};
+use zip_backend::{ZipArchiveReader, ZipArchiveWriter};
+
+
+/// The archive formats which are supported as backend.
+///
+/// "Zip" archives support per-file compression and AES encryption.
+/// "Tar", "TarGz" and "TarZst" archives additionally store the owner of
+/// every entry.
+/// "Dir" mirrors the entries uncompressed into a plain directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+    Dir,
+}
+
+impl ArchiveFormat {
+    /// Determines the archive format of the config. An explicit `format`
+    /// takes precedence over the extension of the `zip_path`; unknown
+    /// extensions select ZIP.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config with the `format` and the `zip_path`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ArchiveFormat)` - The archive format.
+    /// * `Err(Error)` - If the `format` is unknown.
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        match config.format.as_str() {
+            "" => Ok(Self::from_path(&config.zip_path)),
+            "zip" => Ok(Self::Zip),
+            "tar" => Ok(Self::Tar),
+            "tar.gz" | "tgz" => Ok(Self::TarGz),
+            "tar.zst" | "tzst" => Ok(Self::TarZst),
+            "dir" => Ok(Self::Dir),
+            other => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown archive format: {}", other)
+            ))
+        }
+    }
+
+    /// Determines the archive format from the extension of a path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the archive.
+    ///
+    /// # Returns
+    ///
+    /// * `ArchiveFormat` - The archive format (ZIP for unknown extensions).
+    fn from_path(path: &str) -> Self {
+        let path = path.to_lowercase();
+        if path.ends_with(".tar") {
+            Self::Tar
+        } else if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Self::TarGz
+        } else if path.ends_with(".tar.zst") || path.ends_with(".tzst") {
+            Self::TarZst
+        } else {
+            Self::Zip
+        }
+    }
+
+    /// Returns the name of an archive without its extension
+    /// (e.g. "backup.tar.gz" -> "backup").
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the archive.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The file name without the extension of the format.
+    pub fn archive_stem(&self, path: &Path) -> String {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let extensions: &[&str] = match self {
+            Self::Zip => &[".zip"],
+            Self::Tar => &[".tar"],
+            Self::TarGz => &[".tar.gz", ".tgz"],
+            Self::TarZst => &[".tar.zst", ".tzst"],
+            Self::Dir => &[],
+        };
+
+        for extension in extensions {
+            if name.len() > extension.len()
+            && name.to_lowercase().ends_with(extension) {
+                return name[..name.len() - extension.len()].to_string();
+            }
+        }
+
+        path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+
+/// A single entry which was read from an archive.
+///
+/// # Fields
+///
+/// * `name` - The path of the entry in the archive (without a trailing slash
+///   for directories).
+/// * `kind` - The kind of the entry (symlinks contain their target).
+/// * `size` - The uncompressed size of the entry.
+/// * `crc32` - The stored CRC32 of the entry (if the format has one and the
+///   entry isn't encrypted).
+/// * `metadata` - The modification time, permissions and owner of the entry.
+#[derive(Debug, Clone)]
+pub struct EntryInfo {
+    pub name: String,
+    pub kind: EntryKind,
+    pub size: u64,
+    pub crc32: Option<u32>,
+    pub metadata: FileMetadata
+}
+
+impl EntryInfo {
+    /// Checks if the name of the entry is a relative path which stays inside
+    /// the folder the archive is extracted to.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the entry can be extracted safely.
+    pub fn has_safe_name(&self) -> bool {
+        Path::new(&self.name).components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    }
+}
+
+
+/// Function which prepares the content of a file for an archive writer
+/// (e.g. compresses it). It runs on the worker pool.
+///
+/// Arguments: path in the archive, content of the file, metadata of the file.
+pub type FileEncoder = Box<
+    dyn Fn(&str, Vec<u8>, FileMetadata) -> io::Result<Vec<u8>> + Send + Sync
+>;
+
+/// Callback which receives every entry of an archive together with its
+/// content.
+pub type EntryCallback<'a> = dyn FnMut(&EntryInfo, &mut dyn Read)
+    -> io::Result<()> + 'a;
+
+
+/// Writes the entries of a new archive.
+/// Parent directories have to be written before their content.
+pub trait ArchiveWriter {
+    /// Returns the function which prepares the content of the files of a
+    /// task. The prepared content is passed to `write_file`.
+    ///
+    /// # Arguments
+    ///
+    /// * `compression` - The compression settings of the task.
+    fn file_encoder(&self, compression: Compression) -> FileEncoder;
+
+    /// Writes a file which was prepared by the encoder of `file_encoder`.
+    fn write_file(&mut self, name: &str, data: Vec<u8>, metadata: FileMetadata)
+    -> io::Result<()>;
+
+    /// Writes a large file while it is read from `content`, without keeping
+    /// it in memory. `size` is the size of the file when it was opened.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The path of the file within the archive.
+    /// * `content` - The content of the file.
+    /// * `size` - The expected size of the content.
+    /// * `compression` - The compression settings of the task.
+    /// * `metadata` - The modification time and permissions of the file.
+    fn write_stream(
+        &mut self, name: &str, content: &mut dyn Read, size: u64,
+        compression: Compression, metadata: FileMetadata
+    ) -> io::Result<()>;
+
+    /// Writes a directory (without a trailing slash in `name`).
+    fn write_directory(&mut self, name: &str, metadata: FileMetadata)
+    -> io::Result<()>;
+
+    /// Writes a symbolic link which points to `target`.
+    fn write_symlink(&mut self, name: &str, target: &Path) -> io::Result<()>;
+
+    /// Sets the archive of the previous update, which `copy_unchanged`
+    /// copies the files from.
+    fn copy_unchanged_from(&mut self, _archive: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Copies a file which didn't change since the previous update from the
+    /// archive of `copy_unchanged_from` without reading and compressing it
+    /// again.
+    ///
+    /// Returns false if the file cannot be copied (e.g. tar archives are
+    /// compressed as a whole, or the file is encrypted); it has to be
+    /// written with `write_file` then.
+    fn copy_unchanged(&mut self, _name: &str) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    /// Marks the path of a copy task which was written completely: a mirror
+    /// removes all entries below it which weren't written by this run when
+    /// it is finalized. Archives are written from scratch anyway.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The path of the copy task.
+    /// * `kept` - The paths of the other copy tasks (including the ones which
+    ///   weren't selected); their entries (and their parent directories)
+    ///   are kept.
+    fn prune_below(&mut self, _name: &str, _kept: &[String]) {}
+
+    /// Writes all remaining structures and closes the archive.
+    fn finalize(self: Box<Self>) -> io::Result<()>;
+}
+
+/// Reads the entries of an existing archive.
+pub trait ArchiveReader {
+    /// Returns all entries of the archive without reading their content.
+    fn list_entries(&mut self) -> io::Result<Vec<EntryInfo>>;
+
+    /// Reads every entry of the archive in the order of the archive and
+    /// passes it to the callback. Encrypted entries are decrypted.
+    fn read_entries(&mut self, callback: &mut EntryCallback) -> io::Result<()>;
+}
+
+
+/// Creates the writer for an archive in the format of the config. An
+/// existing archive is overwritten.
+///
+/// # Arguments
+///
+/// * `config` - The config with the format of the archive.
+/// * `path` - The path the archive is written to.
+/// * `password` - The password for AES encryption (ZIP only).
+///
+/// # Returns
+///
+/// * `Ok(Box<dyn ArchiveWriter>)` - The writer.
+/// * `Err(Error)` - If the archive cannot be created or the format doesn't
+///   support the settings.
+pub fn create_writer(config: &Config, path: &str, password: Option<String>)
+-> Result<Box<dyn ArchiveWriter>, Error> {
+    let format = ArchiveFormat::from_config(config)?;
+    if format != ArchiveFormat::Zip && password.is_some() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Encryption is only supported for ZIP archives"
+        ));
+    }
+
+    Ok(match format {
+        ArchiveFormat::Zip => {
+            Box::new(ZipArchiveWriter::create(path, password)?)
+        },
+        ArchiveFormat::Dir => Box::new(DirArchiveWriter::create(path)?),
+        tar_format => Box::new(TarArchiveWriter::create(
+            path, tar_format, config.compression_level
+        )?)
+    })
+}
+
+/// Opens the archive of the config for reading.
+///
+/// # Arguments
+///
+/// * `config` - The config with the path, the format and the password source
+///   of the archive.
+///
+/// # Returns
+///
+/// * `Ok(Box<dyn ArchiveReader>)` - The reader.
+/// * `Err(Error)` - If the archive cannot be opened.
+pub fn open_reader(config: &Config) -> Result<Box<dyn ArchiveReader>, Error> {
+    open_reader_with_password(config, None)
+}
+
+/// Opens the archive of the config for reading with a password which was
+/// already read (e.g. for writing a new archive), so it isn't asked for
+/// again. The password source is only used if the password doesn't fit.
+///
+/// # Arguments
+///
+/// * `config` - The config with the path, the format and the password source
+///   of the archive.
+/// * `password` - The password which was already read (if any).
+///
+/// # Returns
+///
+/// * `Ok(Box<dyn ArchiveReader>)` - The reader.
+/// * `Err(Error)` - If the archive cannot be opened.
+pub fn open_reader_with_password(config: &Config, password: Option<String>)
+-> Result<Box<dyn ArchiveReader>, Error> {
+    Ok(match ArchiveFormat::from_config(config)? {
+        ArchiveFormat::Zip => Box::new(ZipArchiveReader::open(
+            &config.zip_path, config.password_source.clone(), password
+        )?),
+        ArchiveFormat::Dir => {
+            Box::new(DirArchiveReader::open(&config.zip_path)?)
+        },
+        tar_format => {
+            Box::new(TarArchiveReader::open(&config.zip_path, tar_format)?)
+        }
+    })
+}
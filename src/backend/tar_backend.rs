@@ -0,0 +1,298 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Error, Read, Write};
+use std::path::Path;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use tar::{Archive, Builder, EntryType, Header};
+
+use crate::backend::{
+    ArchiveFormat, ArchiveReader, ArchiveWriter, EntryCallback, EntryInfo,
+    FileEncoder
+};
+use crate::model::archive_entry::EntryKind;
+use crate::model::compression::Compression;
+use crate::model::file_metadata::FileMetadata;
+
+
+/// Writes a tar archive which is optionally compressed as a whole with gzip
+/// or zstd. Besides the modification time and the permissions, the owner of
+/// every entry is stored.
+pub struct TarArchiveWriter {
+    /// The writer of the tar archive.
+    builder: Builder<TarStream>
+}
+
+impl TarArchiveWriter {
+    /// Creates a new tar archive. An existing file is overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the tar archive.
+    /// * `format` - The format of the archive (plain, gzip or zstd).
+    /// * `level` - The compression level (`None` for the default level).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TarArchiveWriter)` - The writer.
+    /// * `Err(Error)` - If the file cannot be created.
+    pub fn create(path: &str, format: ArchiveFormat, level: Option<i64>)
+    -> Result<Self, Error> {
+        let file = BufWriter::new(File::create(path)?);
+        let stream = match format {
+            ArchiveFormat::TarGz => {
+                let level = level.map_or(6, |level| level.clamp(0, 9) as u32);
+                TarStream::Gz(GzEncoder::new(
+                    file, flate2::Compression::new(level)
+                ))
+            },
+            ArchiveFormat::TarZst => {
+                let level = level.map_or(0, |level| level.clamp(-7, 22) as i32);
+                TarStream::Zst(zstd::Encoder::new(file, level)?)
+            },
+            _ => TarStream::Plain(file)
+        };
+
+        Ok(Self { builder: Builder::new(stream) })
+    }
+
+    /// Creates the header of an entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry_type` - The type of the entry.
+    /// * `size` - The size of the content of the entry.
+    /// * `metadata` - The metadata of the entry.
+    /// * `default_mode` - The permissions if the metadata has none.
+    ///
+    /// # Returns
+    ///
+    /// * `Header` - The header of the entry.
+    fn header(
+        entry_type: EntryType,
+        size: u64,
+        metadata: FileMetadata,
+        default_mode: u32
+    ) -> Header {
+        let mtime = metadata.mtime
+            .unwrap_or_else(|| chrono::Local::now().timestamp());
+
+        let mut header = Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(metadata.permissions.unwrap_or(default_mode));
+        header.set_mtime(u64::try_from(mtime).unwrap_or_default());
+        header.set_uid(u64::from(metadata.uid.unwrap_or_default()));
+        header.set_gid(u64::from(metadata.gid.unwrap_or_default()));
+        header
+    }
+}
+
+impl ArchiveWriter for TarArchiveWriter {
+    fn file_encoder(&self, _compression: Compression) -> FileEncoder {
+        // The archive is compressed as a whole while it is written
+        Box::new(|_name, content, _metadata| Ok(content))
+    }
+
+    fn write_file(&mut self, name: &str, data: Vec<u8>, metadata: FileMetadata)
+    -> io::Result<()> {
+        let mut header = Self::header(
+            EntryType::Regular, data.len() as u64, metadata, 0o644
+        );
+        self.builder.append_data(&mut header, name, data.as_slice())
+    }
+
+    fn write_stream(
+        &mut self, name: &str, content: &mut dyn Read, size: u64,
+        _compression: Compression, metadata: FileMetadata
+    ) -> io::Result<()> {
+        let mut header = Self::header(
+            EntryType::Regular, size, metadata, 0o644
+        );
+
+        // The header holds the size, so a file which changed while it is read
+        // is cut off or padded with zeros to keep the archive readable
+        let content = content.take(size).chain(io::repeat(0)).take(size);
+        self.builder.append_data(&mut header, name, content)
+    }
+
+    fn write_directory(&mut self, name: &str, metadata: FileMetadata)
+    -> io::Result<()> {
+        let mut header = Self::header(
+            EntryType::Directory, 0, metadata, 0o755
+        );
+        self.builder.append_data(&mut header, name, io::empty())
+    }
+
+    fn write_symlink(&mut self, name: &str, target: &Path) -> io::Result<()> {
+        let mut header = Self::header(
+            EntryType::Symlink, 0, FileMetadata::default(), 0o777
+        );
+        self.builder.append_link(&mut header, name, target)
+    }
+
+    fn finalize(self: Box<Self>) -> io::Result<()> {
+        self.builder.into_inner()?.finish()
+    }
+}
+
+
+/// The output stream of a tar archive.
+///
+/// "Plain" streams write the tar archive as it is.
+/// "Gz" and "Zst" streams compress the tar archive with gzip or zstd.
+enum TarStream {
+    Plain(BufWriter<File>),
+    Gz(GzEncoder<BufWriter<File>>),
+    Zst(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl TarStream {
+    /// Writes the end of the compressed stream and flushes the file.
+    fn finish(self) -> io::Result<()> {
+        let mut file = match self {
+            TarStream::Plain(file) => file,
+            TarStream::Gz(encoder) => encoder.finish()?,
+            TarStream::Zst(encoder) => encoder.finish()?
+        };
+        file.flush()
+    }
+}
+
+impl Write for TarStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TarStream::Plain(file) => file.write(buf),
+            TarStream::Gz(encoder) => encoder.write(buf),
+            TarStream::Zst(encoder) => encoder.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TarStream::Plain(file) => file.flush(),
+            TarStream::Gz(encoder) => encoder.flush(),
+            TarStream::Zst(encoder) => encoder.flush()
+        }
+    }
+}
+
+
+/// Reads a tar archive. Tar archives can only be read sequentially, so the
+/// archive is reopened for every pass.
+pub struct TarArchiveReader {
+    /// The path of the tar archive.
+    path: String,
+
+    /// The format of the archive (plain, gzip or zstd).
+    format: ArchiveFormat
+}
+
+impl TarArchiveReader {
+    /// Opens a tar archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the tar archive.
+    /// * `format` - The format of the archive (plain, gzip or zstd).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TarArchiveReader)` - The reader.
+    /// * `Err(Error)` - If the file doesn't exist.
+    pub fn open(path: &str, format: ArchiveFormat) -> Result<Self, Error> {
+        File::open(path)?;
+        Ok(Self { path: path.to_string(), format })
+    }
+
+    /// Opens the (decompressed) tar stream from the start.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Archive)` - The tar archive.
+    /// * `Err(Error)` - If the file cannot be opened.
+    fn archive(&self) -> Result<Archive<Box<dyn Read>>, Error> {
+        let file = BufReader::new(File::open(&self.path)?);
+        let stream: Box<dyn Read> = match self.format {
+            ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+            ArchiveFormat::TarZst => Box::new(zstd::Decoder::new(file)?),
+            _ => Box::new(file)
+        };
+        Ok(Archive::new(stream))
+    }
+
+    /// Returns the information about a tar entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The tar entry.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(EntryInfo))` - The information about the entry.
+    /// * `Ok(None)` - If the entry is neither a file, a directory nor a
+    ///   symlink (e.g. a device file).
+    /// * `Err(Error)` - If the header is invalid.
+    fn entry_info<R: Read>(entry: &tar::Entry<R>)
+    -> Result<Option<EntryInfo>, Error> {
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            EntryType::Regular | EntryType::Continuous => EntryKind::File,
+            EntryType::Directory => EntryKind::Directory,
+            EntryType::Symlink => match entry.link_name()? {
+                Some(target) => EntryKind::Symlink(target.into_owned()),
+                None => return Ok(None)
+            },
+            _ => return Ok(None)
+        };
+
+        let path = entry.path()?;
+        let name = path.to_string_lossy();
+        let name = name.trim_start_matches("./").trim_end_matches('/');
+
+        Ok(Some(EntryInfo {
+            name: name.to_string(),
+            kind,
+            size: entry.size(),
+            crc32: None,
+            metadata: FileMetadata {
+                mtime: header.mtime().ok()
+                    .and_then(|mtime| i64::try_from(mtime).ok()),
+                permissions: header.mode().ok().map(|mode| mode & 0o7777),
+                uid: header.uid().ok()
+                    .and_then(|uid| u32::try_from(uid).ok()),
+                gid: header.gid().ok()
+                    .and_then(|gid| u32::try_from(gid).ok())
+            }
+        }))
+    }
+}
+
+impl ArchiveReader for TarArchiveReader {
+    fn list_entries(&mut self) -> io::Result<Vec<EntryInfo>> {
+        let mut entries = Vec::new();
+        for entry in self.archive()?.entries()? {
+            if let Some(info) = Self::entry_info(&entry?)? {
+                entries.push(info);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn read_entries(&mut self, callback: &mut EntryCallback)
+    -> io::Result<()> {
+        for entry in self.archive()?.entries()? {
+            let mut entry = entry?;
+            let Some(info) = Self::entry_info(&entry)? else { continue; };
+
+            // Like in ZIP archives, the content of a symlink entry is the
+            // target path
+            if let EntryKind::Symlink(target) = &info.kind {
+                let target = target.to_string_lossy().to_string();
+                callback(&info, &mut target.as_bytes())?;
+            } else {
+                callback(&info, &mut entry)?;
+            }
+        }
+        Ok(())
+    }
+}
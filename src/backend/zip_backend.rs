@@ -0,0 +1,365 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{
+    self, BufReader, BufWriter, Cursor, Error, ErrorKind, Read, Write
+};
+use std::path::{Path, PathBuf};
+use zip::read::ZipFile;
+use zip::write::{FileOptions, FullFileOptions};
+use zip::{AesMode, ZipArchive, ZipWriter};
+
+use crate::backend::{
+    ArchiveReader, ArchiveWriter, EntryCallback, EntryInfo, FileEncoder
+};
+use crate::model::archive_entry::EntryKind;
+use crate::model::compression::Compression;
+use crate::model::file_metadata::FileMetadata;
+use crate::model::password_source::PasswordSource;
+
+
+/// Writes a ZIP archive. Every file is compressed (and encrypted) into an
+/// independent in-memory ZIP archive on the worker pool, which is appended
+/// to the actual archive without recompression.
+///
+/// Only the content of files is encrypted: the names of all entries and the
+/// targets of symbolic links are stored in plaintext.
+pub struct ZipArchiveWriter {
+    /// The writer of the ZIP archive.
+    zip: ZipWriter<BufWriter<File>>,
+
+    /// The password for AES encryption (if enabled).
+    password: Option<String>,
+
+    /// The archive of the previous update, which unchanged files are copied
+    /// from (only in watch mode).
+    previous: Option<ZipArchive<BufReader<File>>>,
+
+    /// The names of the written entries. Merged archives bypass the check
+    /// for duplicate names of the ZIP writer.
+    names: HashSet<String>
+}
+
+impl ZipArchiveWriter {
+    /// Creates a new ZIP archive. An existing file is overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the ZIP archive.
+    /// * `password` - The password for AES encryption (if enabled).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ZipArchiveWriter)` - The writer.
+    /// * `Err(Error)` - If the file cannot be created.
+    pub fn create(path: &str, password: Option<String>)
+    -> Result<Self, Error> {
+        let file = File::create(path)?;
+        Ok(Self {
+            zip: ZipWriter::new(BufWriter::new(file)),
+            password,
+            previous: None,
+            names: HashSet::new()
+        })
+    }
+
+    /// Registers the name of a new entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the entry.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the name is new.
+    /// * `Err(Error)` - If an entry with this name was already written.
+    fn add_name(&mut self, name: &str) -> Result<(), Error> {
+        if !self.names.insert(name.to_string()) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Duplicate entry in the archive: {}", name)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the options of a file entry: the compression of the file
+    /// with its modification time and permissions (and the encryption).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The path of the file within the archive.
+    /// * `size` - The size of the file.
+    /// * `compression` - The compression settings of the task.
+    /// * `metadata` - The modification time and permissions of the file.
+    /// * `password` - The password for AES encryption (if enabled).
+    ///
+    /// # Returns
+    ///
+    /// * `FullFileOptions` - The options of the entry.
+    fn file_options<'a>(
+        name: &str, size: u64, compression: Compression,
+        metadata: FileMetadata, password: Option<&'a str>
+    ) -> FullFileOptions<'a> {
+        let compression = compression.for_file(name);
+        let options = metadata.zip_options()
+            .compression_method(compression.method)
+            .compression_level(compression.level)
+            .large_file(size >= u64::from(u32::MAX));
+        match password {
+            Some(password) => {
+                options.with_aes_encryption(AesMode::Aes256, password)
+            },
+            None => options
+        }
+    }
+}
+
+impl ArchiveWriter for ZipArchiveWriter {
+    fn file_encoder(&self, compression: Compression) -> FileEncoder {
+        let password = self.password.clone();
+        Box::new(move |name, content, metadata| {
+            let options = Self::file_options(
+                name, content.len() as u64, compression, metadata,
+                password.as_deref()
+            );
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            writer.start_file(name, options)?;
+            writer.write_all(&content)?;
+            Ok(writer.finish()?.into_inner())
+        })
+    }
+
+    fn write_file(&mut self, name: &str, data: Vec<u8>,
+                  _metadata: FileMetadata) -> io::Result<()> {
+        self.add_name(name)?;
+
+        // Append the pre-compressed file without recompressing it
+        self.zip.merge_archive(ZipArchive::new(Cursor::new(data))?)?;
+        Ok(())
+    }
+
+    fn write_stream(
+        &mut self, name: &str, content: &mut dyn Read, size: u64,
+        compression: Compression, metadata: FileMetadata
+    ) -> io::Result<()> {
+        self.add_name(name)?;
+        let options = Self::file_options(
+            name, size, compression, metadata, self.password.as_deref()
+        );
+        self.zip.start_file(name, options)?;
+        io::copy(content, &mut self.zip)?;
+        Ok(())
+    }
+
+    fn write_directory(&mut self, name: &str, metadata: FileMetadata)
+    -> io::Result<()> {
+        let name = format!("{}/", name);
+        self.add_name(&name)?;
+        self.zip.add_directory(name, metadata.zip_options())?;
+        Ok(())
+    }
+
+    fn write_symlink(&mut self, name: &str, target: &Path) -> io::Result<()> {
+        self.add_name(name)?;
+
+        // The target isn't encrypted, like the names of the entries
+        self.zip.add_symlink::<_, _, ()>(
+            name, target.to_string_lossy(), FileOptions::default()
+        )?;
+        Ok(())
+    }
+
+    fn copy_unchanged_from(&mut self, archive: &Path) -> io::Result<()> {
+        let file = File::open(archive)?;
+        self.previous = Some(ZipArchive::new(BufReader::new(file))?);
+        Ok(())
+    }
+
+    fn copy_unchanged(&mut self, name: &str) -> io::Result<bool> {
+        let Some(previous) = self.previous.as_mut() else {
+            return Ok(false);
+        };
+        let Some(index) = previous.index_for_name(name) else {
+            return Ok(false);
+        };
+
+        // Copied entries lose their encryption header, so encrypted entries
+        // are decrypted and encrypted again
+        if previous.by_index_raw(index)?.encrypted() {
+            return Ok(false);
+        }
+
+        // Copy the compressed (and encrypted) data as it is
+        if self.names.contains(name) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Duplicate entry in the archive: {}", name)
+            ));
+        }
+        self.zip.raw_copy_file(previous.by_index_raw(index)?)?;
+        self.names.insert(name.to_string());
+        Ok(true)
+    }
+
+    fn finalize(self: Box<Self>) -> io::Result<()> {
+        self.zip.finish()?;
+        Ok(())
+    }
+}
+
+
+/// Reads a ZIP archive. The password of encrypted archives is only read
+/// when the content of an encrypted entry is needed.
+pub struct ZipArchiveReader {
+    /// The ZIP archive.
+    archive: ZipArchive<BufReader<File>>,
+
+    /// The source of the password for encrypted archives.
+    password_source: Option<PasswordSource>,
+
+    /// The password which was already read (if any), tried before the
+    /// password source.
+    password: Option<String>
+}
+
+impl ZipArchiveReader {
+    /// Opens a ZIP archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the ZIP archive.
+    /// * `password_source` - The source of the password for encrypted
+    ///   archives.
+    /// * `password` - The password which was already read (if any).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ZipArchiveReader)` - The reader.
+    /// * `Err(Error)` - If the file isn't a ZIP archive.
+    pub fn open(
+        path: &str, password_source: Option<PasswordSource>,
+        password: Option<String>
+    ) -> Result<Self, Error> {
+        let archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+        Ok(Self { archive, password_source, password })
+    }
+
+    /// Returns the password for the encrypted entries of the archive: the
+    /// password which was already read if it decrypts them, otherwise the
+    /// password is read from its source.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(String))` - The password, if the archive is encrypted.
+    /// * `Ok(None)` - If the archive isn't encrypted.
+    /// * `Err(Error)` - If the password cannot be read.
+    fn archive_password(&mut self) -> Result<Option<String>, Error> {
+        if let Some(password) = &self.password {
+            for i in 0..self.archive.len() {
+                if !self.archive.by_index_raw(i)?.encrypted() {
+                    continue;
+                }
+                if self.archive.by_index_decrypt(i, password.as_bytes())
+                    .is_ok() {
+                    return Ok(Some(password.clone()));
+                }
+                break;
+            }
+        }
+
+        PasswordSource::read_archive_password(
+            &mut self.archive, self.password_source.as_ref()
+        )
+    }
+
+    /// Returns the information about a ZIP entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The ZIP entry.
+    /// * `target` - The target path (for symlink entries).
+    ///
+    /// # Returns
+    ///
+    /// * `EntryInfo` - The information about the entry.
+    fn entry_info(entry: &ZipFile, target: Option<String>) -> EntryInfo {
+        let kind = if entry.is_dir() {
+            EntryKind::Directory
+        } else if let Some(target) = target {
+            EntryKind::Symlink(PathBuf::from(target))
+        } else {
+            EntryKind::File
+        };
+
+        EntryInfo {
+            name: entry.name().trim_end_matches('/').to_string(),
+            kind,
+            size: entry.size(),
+            // AES encrypted entries are authenticated instead (AE-2)
+            crc32: (!entry.encrypted()).then(|| entry.crc32()),
+            metadata: FileMetadata::from_zip_entry(entry)
+        }
+    }
+}
+
+impl ArchiveReader for ZipArchiveReader {
+    fn list_entries(&mut self) -> io::Result<Vec<EntryInfo>> {
+        let mut entries = Vec::new();
+        let mut password = None;
+
+        for i in 0..self.archive.len() {
+            // Raw access, the names of encrypted entries are not encrypted
+            let raw = self.archive.by_index_raw(i)?;
+            if !raw.is_symlink() || raw.is_dir() {
+                entries.push(Self::entry_info(&raw, None));
+                continue;
+            }
+            let encrypted = raw.encrypted();
+            drop(raw);
+
+            // The content of a symlink entry is the target path
+            let mut entry = if encrypted {
+                if password.is_none() {
+                    password = Some(PasswordSource::read_password(
+                        self.password_source.as_ref(), false
+                    )?);
+                }
+                let password = password.as_deref().unwrap_or_default();
+                self.archive.by_index_decrypt(i, password.as_bytes())?
+            } else {
+                self.archive.by_index(i)?
+            };
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            entries.push(Self::entry_info(&entry, Some(target)));
+        }
+
+        Ok(entries)
+    }
+
+    fn read_entries(&mut self, callback: &mut EntryCallback)
+    -> io::Result<()> {
+        let password = self.archive_password()?;
+
+        for i in 0..self.archive.len() {
+            let mut entry = match &password {
+                Some(password) => {
+                    self.archive.by_index_decrypt(i, password.as_bytes())?
+                },
+                None => self.archive.by_index(i)?
+            };
+
+            if entry.is_symlink() && !entry.is_dir() {
+                let mut target = String::new();
+                entry.read_to_string(&mut target)?;
+                let info = Self::entry_info(&entry, Some(target.clone()));
+                callback(&info, &mut target.as_bytes())?;
+            } else {
+                let info = Self::entry_info(&entry, None);
+                callback(&info, &mut entry)?;
+            }
+        }
+
+        Ok(())
+    }
+}
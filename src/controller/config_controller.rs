@@ -0,0 +1,999 @@
+use std::fs;
+use std::io::{self, BufRead, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use colored::*;
+use serde_json::{json, Value};
+use toml_edit::{DocumentMut, InlineTable, Item, Table, TableLike};
+
+use crate::model::config_loader::{ConfigFormat, ConfigLoader};
+use crate::model::copy_task::{yes_no_value, CopyTask};
+use crate::model::path_mapping::PathMapping;
+
+
+/// The task fields which are lists; their values are given comma-separated
+/// on the command line (e.g. `--tags work,large`).
+const LIST_FIELDS: &[&str] = &["tags", "only_on_hosts", "only_on_os"];
+
+/// The task fields which are "yes"/"no" settings.
+const YES_NO_FIELDS: &[&str] = &["required", "enabled"];
+
+
+/// A change of the copy tasks of a config file. TOML files are edited with
+/// the change, so their comments and formatting are kept.
+///
+/// # Variants
+///
+/// * `Add` - The task is appended.
+/// * `Remove` - The task with this index is removed.
+/// * `Replace` - The task with this index is replaced (only the changed
+///   fields are written).
+enum TaskChange {
+    Add(Value),
+    Remove(usize),
+    Replace(usize, Value)
+}
+
+
+/// The ConfigController handles the commands which work on the config file
+/// itself instead of an archive: `init`, `task` and `config convert`.
+pub struct ConfigController {
+    /// The path of the config file.
+    path: PathBuf,
+
+    /// The profile whose copy tasks are managed (the top level if `None`).
+    profile: Option<String>
+}
+
+impl ConfigController {
+    /// Creates a new `ConfigController` for a config file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the config file.
+    /// * `profile` - The profile whose copy tasks are managed.
+    ///
+    /// # Returns
+    ///
+    /// * `ConfigController` - A new instance of the config controller.
+    pub fn new(path: &Path, profile: Option<String>) -> ConfigController {
+        ConfigController { path: path.to_path_buf(), profile }
+    }
+
+    /// Converts a config file into another format (JSON, TOML or YAML,
+    /// detected by the extensions). Includes are not resolved, so the
+    /// structure of the file is kept. Comments are lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The path of the config file to convert.
+    /// * `output` - The path of the converted config file. It must not exist.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the config was converted.
+    pub fn convert(input: &Path, output: &Path) -> bool {
+        match Self::convert_file(input, output) {
+            Ok(()) => {
+                println!(
+                    "{}",
+                    format!("Config '{}' converted to '{}'",
+                            input.display(), output.display()).green().bold()
+                );
+                true
+            },
+            Err(e) => {
+                println!(
+                    "{}",
+                    format!("!!! Config could not be converted: {}", e)
+                        .red().bold()
+                );
+                false
+            }
+        }
+    }
+
+    /// Converts a config file into another format.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The path of the config file to convert.
+    /// * `output` - The path of the converted config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the file was converted.
+    /// * `Err(Error)` - If the file cannot be read, converted or written.
+    fn convert_file(input: &Path, output: &Path) -> Result<(), Error> {
+        if output.exists() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("'{}' already exists", output.display())
+            ));
+        }
+
+        let value = ConfigLoader::read_value(input)?;
+        let format = ConfigFormat::from_path(output)?;
+        let content = format.serialize(&value)?;
+
+        // Make sure the converted file can be read again
+        format.parse(&content)?;
+        fs::write(output, content)
+    }
+
+    /// Creates a new config file interactively. An existing file is only
+    /// replaced after confirmation.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the config was created or the user cancelled.
+    pub fn init(&self) -> bool {
+        if let Err(e) = self.init_config() {
+            println!(
+                "{}",
+                format!("!!! Config could not be created: {}", e).red().bold()
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Asks for the settings and the copy tasks and writes the config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the config was written or the user cancelled.
+    /// * `Err(Error)` - If the input cannot be read or the file cannot be
+    ///   written.
+    fn init_config(&self) -> Result<(), Error> {
+        println!("Creating config '{}'", self.path.display());
+        if self.path.exists() && !Self::confirm(&format!(
+            "'{}' already exists. Replace it?", self.path.display()
+        ), false)? {
+            return Ok(());
+        }
+
+        let zip_path = Self::prompt("Archive path", "backup.zip")?;
+        let direction = Self::prompt(
+            "Direction (to_zip, from_zip, verify)", "to_zip"
+        )?;
+        let confirm = if Self::confirm("Confirm changes on disk?", true)? {
+            "yes"
+        } else {
+            "no"
+        };
+
+        println!("Copy tasks (leave the source empty to finish):");
+        let mut tasks = Vec::new();
+        loop {
+            let source = Self::prompt("Source", "")?;
+            if source.is_empty() {
+                break;
+            }
+
+            let task = Self::ask_task(CopyTask {
+                name: String::new(),
+                source,
+                description: String::new(),
+                target: String::new(),
+                symlinks: String::new(),
+                compression: String::new(),
+                compression_level: None,
+                tags: Vec::new(),
+                required: String::new(),
+                enabled: String::new(),
+                only_on_hosts: Vec::new(),
+                only_on_os: Vec::new(),
+                pre_hooks: Vec::new(),
+                post_hooks: Vec::new()
+            })?;
+            Self::print_preview(&task);
+            tasks.push(serde_json::to_value(task)?);
+        }
+
+        let config = json!({
+            "zip_path": zip_path,
+            "direction": direction,
+            "confirm_new": confirm,
+            "confirm_overwrite": confirm,
+            "confirm_delete": confirm,
+            "copy_tasks": tasks
+        });
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.write_config(&config, None)?;
+
+        println!("{}", format!("Config '{}' created", self.path.display())
+            .green().bold());
+        Ok(())
+    }
+
+    /// Executes a `task` command on the copy tasks of the config file.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - "list", "add", "remove" or "edit".
+    /// * `selector` - The number, name or description of the task (for
+    ///   "remove" and "edit").
+    /// * `fields` - The task fields given on the command line.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the command succeeded.
+    pub fn task(
+        &self, action: &str, selector: Option<&str>,
+        fields: &[(String, String)]
+    ) -> bool {
+        let result = match (action, selector) {
+            ("list", None) => self.list_tasks(),
+            ("add", None) => self.add_task(fields),
+            ("remove", Some(selector)) => self.remove_task(selector),
+            ("edit", Some(selector)) => self.edit_task(selector, fields),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Usage: zipsync task list | add [--<field> <value>] | \
+                 remove <task> | edit <task> [--<field> <value>]"
+            ))
+        };
+
+        if let Err(e) = result {
+            println!("{}", format!("!!! {}", e).red().bold());
+            return false;
+        }
+        true
+    }
+
+    /// Lists the copy tasks together with the path each task produces in the
+    /// archive.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the tasks were listed.
+    /// * `Err(Error)` - If the config file cannot be read.
+    fn list_tasks(&self) -> Result<(), Error> {
+        let mut config = ConfigLoader::read_value(&self.path)?;
+        let tasks = self.tasks_mut(&mut config)?;
+        if tasks.is_empty() {
+            println!("No copy tasks in '{}'", self.path.display());
+        }
+
+        for (index, task) in tasks.iter().enumerate() {
+            let task: CopyTask = serde_json::from_value(task.clone())?;
+            let label = if task.name.is_empty() {
+                task.description.clone()
+            } else {
+                format!("{} - {}", task.name, task.description)
+            };
+            println!("{}", format!("{}. {}", index + 1, label).bold());
+            Self::print_preview(&task);
+        }
+
+        Ok(())
+    }
+
+    /// Adds a copy task. Fields which are not given on the command line are
+    /// asked for.
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - The task fields given on the command line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the task was added.
+    /// * `Err(Error)` - If the config file cannot be read or written.
+    fn add_task(&self, fields: &[(String, String)]) -> Result<(), Error> {
+        let mut config = ConfigLoader::read_value(&self.path)?;
+        let mut task = json!({ "source": "", "description": "", "target": "" });
+        Self::set_fields(&mut task, fields)?;
+        let mut task: CopyTask = serde_json::from_value(task)?;
+
+        // Ask for everything which isn't given
+        if task.source.is_empty() {
+            task.source = Self::prompt("Source", "")?;
+        }
+        if task.source.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Missing source"));
+        }
+        if fields.iter().all(|(field, _)| field != "target") {
+            task = Self::ask_task(task)?;
+        }
+
+        Self::print_preview(&task);
+        let task = serde_json::to_value(&task)?;
+        self.tasks_mut(&mut config)?.push(task.clone());
+        self.write_config(&config, Some(TaskChange::Add(task)))?;
+        println!("{}", "Task added".green().bold());
+        Ok(())
+    }
+
+    /// Removes a copy task.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The number, name or description of the task.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the task was removed.
+    /// * `Err(Error)` - If the task doesn't exist or the config file cannot
+    ///   be read or written.
+    fn remove_task(&self, selector: &str) -> Result<(), Error> {
+        let mut config = ConfigLoader::read_value(&self.path)?;
+        let tasks = self.tasks_mut(&mut config)?;
+        let index = Self::find_task(tasks, selector)?;
+        let removed = tasks.remove(index);
+
+        self.write_config(&config, Some(TaskChange::Remove(index)))?;
+        println!("{}", format!(
+            "Task removed: {}",
+            removed.get("source").and_then(Value::as_str).unwrap_or_default()
+        ).green().bold());
+        Ok(())
+    }
+
+    /// Changes a copy task. Without fields on the command line, the target,
+    /// description and name are asked for (with the current values as
+    /// defaults).
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The number, name or description of the task.
+    /// * `fields` - The task fields given on the command line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the task was changed.
+    /// * `Err(Error)` - If the task doesn't exist or the config file cannot
+    ///   be read or written.
+    fn edit_task(&self, selector: &str, fields: &[(String, String)])
+    -> Result<(), Error> {
+        let mut config = ConfigLoader::read_value(&self.path)?;
+        let tasks = self.tasks_mut(&mut config)?;
+        let index = Self::find_task(tasks, selector)?;
+
+        if fields.is_empty() {
+            let task: CopyTask = serde_json::from_value(tasks[index].clone())?;
+            let task = Self::ask_task(CopyTask {
+                source: Self::prompt("Source", &task.source)?,
+                ..task
+            })?;
+
+            let fields: Vec<(String, String)> = [
+                ("source", task.source), ("target", task.target),
+                ("description", task.description), ("name", task.name)
+            ].into_iter()
+                .map(|(field, value)| (field.to_string(), value))
+                .collect();
+            Self::set_fields(&mut tasks[index], &fields)?;
+        } else {
+            Self::set_fields(&mut tasks[index], fields)?;
+        }
+
+        let changed = tasks[index].clone();
+        let task: CopyTask = serde_json::from_value(changed.clone())?;
+        Self::print_preview(&task);
+        self.write_config(&config, Some(TaskChange::Replace(index, changed)))?;
+        println!("{}", "Task changed".green().bold());
+        Ok(())
+    }
+
+    /// Asks for the target, the description and the name of a task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task with the current values (used as defaults).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CopyTask)` - The task with the entered values.
+    /// * `Err(Error)` - If the input cannot be read.
+    fn ask_task(mut task: CopyTask) -> Result<CopyTask, Error> {
+        println!(
+            "Target in the archive: empty = archive root, ending with '/' or \
+             without extension = folder, with extension = file name"
+        );
+        task.target = Self::prompt("Target", &task.target)?;
+
+        let description = if task.description.is_empty() {
+            task.source.clone()
+        } else {
+            task.description.clone()
+        };
+        task.description = Self::prompt("Description", &description)?;
+        task.name = Self::prompt("Name (optional)", &task.name)?;
+        Ok(task)
+    }
+
+    /// Prints the path a task produces in the archive and warns about
+    /// targets which are interpreted differently than they may look.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The copy task.
+    fn print_preview(task: &CopyTask) {
+        let mapping = PathMapping::predict(task);
+        let is_dir = mapping.is_dir;
+        let slash = if is_dir { "/" } else { "" };
+        println!("   {} -> {}{}",
+                 task.source, mapping.archive_path.cyan(), slash);
+
+        let source = Path::new(&task.source);
+        if !source.exists() {
+            println!("{}", "   Warning: the source doesn't exist".yellow());
+        }
+        let target = task.target.trim_start_matches('/');
+        let has_extension = Path::new(target).extension().is_some();
+        if is_dir && has_extension {
+            println!("{}", format!(
+                "   Warning: the folder is stored as '{}' in the archive",
+                target
+            ).yellow());
+        } else if !is_dir && !target.is_empty() && !target.ends_with('/')
+        && !has_extension {
+            println!("{}", format!(
+                "   Warning: '{}' has no extension and is used as a folder",
+                target
+            ).yellow());
+        }
+    }
+
+    /// Returns the copy tasks of the selected profile (or of the top level).
+    /// A missing task list is created.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The content of the config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&mut Vec<Value>)` - The copy tasks.
+    /// * `Err(Error)` - If the profile doesn't exist or the config is invalid.
+    fn tasks_mut<'a>(&self, config: &'a mut Value)
+    -> Result<&'a mut Vec<Value>, Error> {
+        let invalid = |message: String| {
+            Error::new(ErrorKind::InvalidData, message)
+        };
+
+        let mut object = config.as_object_mut()
+            .ok_or_else(|| invalid("The config must be an object".into()))?;
+        if let Some(profile) = &self.profile {
+            object = object.get_mut("profiles")
+                .and_then(|profiles| profiles.get_mut(profile))
+                .and_then(Value::as_object_mut)
+                .ok_or_else(|| {
+                    invalid(format!("Unknown profile: {}", profile))
+                })?;
+        }
+
+        object.entry("copy_tasks").or_insert_with(|| json!([]))
+            .as_array_mut()
+            .ok_or_else(|| invalid("'copy_tasks' must be a list".into()))
+    }
+
+    /// Finds a copy task by its number (starting at 1), name or description.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The copy tasks.
+    /// * `selector` - The number, name or description of the task.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - The index of the task.
+    /// * `Err(Error)` - If no task matches.
+    fn find_task(tasks: &[Value], selector: &str) -> Result<usize, Error> {
+        if let Ok(number) = selector.parse::<usize>()
+        && (1..=tasks.len()).contains(&number) {
+            return Ok(number - 1);
+        }
+
+        tasks.iter().position(|task| {
+            ["name", "description"].iter().any(|key| {
+                task.get(key).and_then(Value::as_str) == Some(selector)
+            })
+        }).ok_or_else(|| Error::new(
+            ErrorKind::NotFound, format!("Task not found: {}", selector)
+        ))
+    }
+
+    /// Sets fields of a task object. Unknown fields of the object are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task object.
+    /// * `fields` - The fields and their new values.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the fields were set.
+    /// * `Err(Error)` - If a "yes"/"no" setting has another value.
+    fn set_fields(task: &mut Value, fields: &[(String, String)])
+    -> Result<(), Error> {
+        if let Value::Object(task) = task {
+            for (field, value) in fields {
+                let value = if YES_NO_FIELDS.contains(&field.as_str()) {
+                    let value = yes_no_value(value).ok_or_else(|| Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("{} must be \"yes\" or \"no\": {}",
+                                field, value)
+                    ))?;
+                    Value::String(value.to_string())
+                } else if LIST_FIELDS.contains(&field.as_str()) {
+                    // A comma-separated list (an empty value removes the tags)
+                    value.split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(|tag| Value::String(tag.to_string()))
+                        .collect()
+                } else {
+                    Value::String(value.clone())
+                };
+                task.insert(field.clone(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the config file safely: the tasks are validated, the content is
+    /// written to a temporary file which then replaces the config file, and
+    /// the previous version is kept as "<file>.bak".
+    ///
+    /// A change of an existing TOML file is applied to the file itself, so
+    /// its comments are kept. JSON and YAML files are serialized again with
+    /// their keys in the original order. YAML files with comments are not
+    /// changed, as the comments would be lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The new content of the config file.
+    /// * `change` - The change of the copy tasks (`None` if the file is
+    ///   written as a whole).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the config file was written.
+    /// * `Err(Error)` - If the config is invalid or cannot be written.
+    fn write_config(&self, config: &Value, change: Option<TaskChange>)
+    -> Result<(), Error> {
+        let format = ConfigFormat::from_path(&self.path)?;
+        let existing = match change {
+            Some(_) => Some(fs::read_to_string(&self.path)?),
+            None => None
+        };
+        let content = match (format, existing, change) {
+            (ConfigFormat::Toml, Some(existing), Some(change)) => {
+                self.edit_toml(&existing, change)?
+            },
+            (ConfigFormat::Yaml, Some(existing), _)
+            if Self::has_yaml_comments(&existing) => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    format!(
+                        "'{}' contains comments, which would be lost; edit \
+                         the file by hand or convert it to TOML (zipsync \
+                         config convert)", self.path.display()
+                    )
+                ));
+            },
+            _ => format.serialize(config)?
+        };
+
+        // The written file has to be readable again (with the same content)
+        let mut written = format.parse(&content)?;
+        for task in self.tasks_mut(&mut written)?.iter() {
+            serde_json::from_value::<CopyTask>(task.clone())?;
+        }
+        if written != *config {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The config could not be changed without changing other \
+                 values"
+            ));
+        }
+
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, content)?;
+
+        if self.path.exists() {
+            let mut backup = self.path.clone().into_os_string();
+            backup.push(".bak");
+            fs::copy(&self.path, backup)?;
+        }
+        fs::rename(&temporary, &self.path)
+    }
+
+    /// Applies a change of the copy tasks to the content of a TOML file.
+    /// Tasks may be written as an array of tables (`[[copy_tasks]]`) or as
+    /// an array of inline tables.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the TOML file.
+    /// * `change` - The change of the copy tasks.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The changed content.
+    /// * `Err(Error)` - If the file cannot be parsed or the tasks cannot be
+    ///   found.
+    fn edit_toml(&self, content: &str, change: TaskChange)
+    -> Result<String, Error> {
+        let invalid = |message: String| {
+            Error::new(ErrorKind::InvalidData, message)
+        };
+
+        let mut document: DocumentMut = content.parse()
+            .map_err(|e| invalid(format!("{}", e)))?;
+        let mut table: &mut dyn TableLike = document.as_table_mut();
+        if let Some(profile) = &self.profile {
+            table = table.get_mut("profiles")
+                .and_then(|profiles| profiles.get_mut(profile))
+                .and_then(Item::as_table_like_mut)
+                .ok_or_else(|| {
+                    invalid(format!("Unknown profile: {}", profile))
+                })?;
+        }
+        if table.get("copy_tasks").is_none() {
+            table.insert("copy_tasks", Item::ArrayOfTables(Default::default()));
+        }
+
+        let out_of_range = || invalid("Unknown task".into());
+        match (table.get_mut("copy_tasks"), change) {
+            (Some(Item::ArrayOfTables(tasks)), TaskChange::Add(task)) => {
+                let mut table = Table::new();
+                Self::update_toml_task(&mut table, &json!({}), &task)?;
+                tasks.push(table);
+            },
+            (Some(Item::ArrayOfTables(tasks)), TaskChange::Remove(index)) => {
+                if index >= tasks.len() {
+                    return Err(out_of_range());
+                }
+                tasks.remove(index);
+            },
+            (Some(Item::ArrayOfTables(tasks)),
+             TaskChange::Replace(index, task)) => {
+                let table = tasks.get_mut(index).ok_or_else(out_of_range)?;
+                let old = Self::toml_task_value(table)?;
+                Self::update_toml_task(table, &old, &task)?;
+            },
+            (Some(Item::Value(toml_edit::Value::Array(tasks))), change) => {
+                match change {
+                    TaskChange::Add(task) => {
+                        let mut table = InlineTable::new();
+                        Self::update_toml_task(&mut table, &json!({}), &task)?;
+
+                        // On a new line like the other tasks (without the
+                        // comments before them)
+                        let mut value = toml_edit::Value::from(table);
+                        if let Some((_, indent)) = tasks.iter().last()
+                            .and_then(|last| last.decor().prefix())
+                            .and_then(|prefix| prefix.as_str())
+                            .and_then(|prefix| prefix.rsplit_once('\n')) {
+                            let prefix = format!("\n{}", indent);
+                            value.decor_mut().set_prefix(prefix);
+                        }
+                        tasks.push_formatted(value);
+                    },
+                    TaskChange::Remove(index) => {
+                        if index >= tasks.len() {
+                            return Err(out_of_range());
+                        }
+                        tasks.remove(index);
+                    },
+                    TaskChange::Replace(index, task) => {
+                        let table = tasks.get_mut(index)
+                            .and_then(toml_edit::Value::as_inline_table_mut)
+                            .ok_or_else(out_of_range)?;
+                        let old = Self::toml_task_value(table)?;
+                        Self::update_toml_task(table, &old, &task)?;
+                    }
+                }
+            },
+            _ => return Err(invalid("'copy_tasks' must be a list".into()))
+        }
+
+        Ok(document.to_string())
+    }
+
+    /// Returns the content of a task table of a TOML file.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The task table (a table or an inline table).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value)` - The fields of the task.
+    /// * `Err(Error)` - If the table cannot be read.
+    fn toml_task_value(table: &dyn TableLike) -> Result<Value, Error> {
+        let mut document = DocumentMut::new();
+        for (key, item) in table.iter() {
+            document.insert(key, item.clone());
+        }
+        toml::from_str(&document.to_string())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the fields of a task which differ from the old task into a
+    /// TOML table; fields which were removed are removed from the table.
+    /// Unchanged fields keep their formatting and comments.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The task table (a table or an inline table).
+    /// * `old` - The old fields of the task.
+    /// * `task` - The new fields of the task.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the table was changed.
+    /// * `Err(Error)` - If a value cannot be written to TOML.
+    fn update_toml_task(table: &mut dyn TableLike, old: &Value, task: &Value)
+    -> Result<(), Error> {
+        let empty = serde_json::Map::new();
+        let fields = task.as_object().unwrap_or(&empty);
+        for (key, value) in fields {
+            if old.get(key) != Some(value) {
+                table.insert(key, Item::Value(Self::toml_value(value)?));
+            }
+        }
+
+        let removed: Vec<String> = old.as_object().unwrap_or(&empty).keys()
+            .filter(|key| !fields.contains_key(*key))
+            .cloned()
+            .collect();
+        for key in removed {
+            table.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// Converts a JSON value into a TOML value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The JSON value.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(toml_edit::Value)` - The TOML value.
+    /// * `Err(Error)` - If the value is `null`, which TOML doesn't support.
+    fn toml_value(value: &Value) -> Result<toml_edit::Value, Error> {
+        Ok(match value {
+            Value::String(text) => text.as_str().into(),
+            Value::Bool(flag) => (*flag).into(),
+            Value::Number(number) => match number.as_i64() {
+                Some(integer) => integer.into(),
+                None => number.as_f64().unwrap_or_default().into()
+            },
+            Value::Array(items) => {
+                let mut array = toml_edit::Array::new();
+                for item in items {
+                    array.push(Self::toml_value(item)?);
+                }
+                array.into()
+            },
+            Value::Object(fields) => {
+                let mut table = InlineTable::new();
+                for (key, item) in fields {
+                    table.insert(key, Self::toml_value(item)?);
+                }
+                table.into()
+            },
+            Value::Null => return Err(Error::new(
+                ErrorKind::InvalidData, "TOML has no null values"
+            ))
+        })
+    }
+
+    /// Checks if the content of a YAML file contains comments. A `#` inside
+    /// a string is counted as well, so the check errs on the safe side.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the YAML file.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the file (probably) contains comments.
+    fn has_yaml_comments(content: &str) -> bool {
+        content.lines().any(|line| {
+            line.trim_start().starts_with('#') || line.contains(" #")
+        })
+    }
+
+    /// Asks a question on the terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - The question.
+    /// * `default` - The answer if nothing is entered.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The answer.
+    /// * `Err(Error)` - If the input cannot be read.
+    fn prompt(question: &str, default: &str) -> Result<String, Error> {
+        if default.is_empty() {
+            print!("{}: ", question);
+        } else {
+            print!("{} [{}]: ", question, default);
+        }
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        let answer = answer.trim();
+        Ok(if answer.is_empty() { default } else { answer }.to_string())
+    }
+
+    /// Asks a yes/no question on the terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - The question.
+    /// * `default` - The answer if nothing is entered.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - True if the answer is yes.
+    /// * `Err(Error)` - If the input cannot be read.
+    fn confirm(question: &str, default: bool) -> Result<bool, Error> {
+        let default = if default { "yes" } else { "no" };
+        let answer = Self::prompt(&format!("{} (yes/no)", question), default)?;
+        Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Converts pairs of field names and values into task fields.
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter()
+            .map(|(field, value)| (field.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn task_commands_keep_toml_comments() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.toml");
+        fs::write(&path, "\
+# Settings
+zip_path = \"backup.zip\"
+direction = \"to_zip\"
+confirm_new = \"no\"
+confirm_overwrite = \"no\"
+confirm_delete = \"no\"
+
+# Documents
+[[copy_tasks]]
+name = \"docs\" # edited below
+source = \"/data/docs\"
+description = \"Docs\"
+target = \"\"
+
+# Photos
+[[copy_tasks]]
+name = \"photos\"
+source = \"/data/photos\"
+description = \"Photos\"
+target = \"\"
+").unwrap();
+
+        let controller = ConfigController::new(&path, None);
+        assert!(controller.task("add", None, &fields(&[
+            ("source", "/data/notes"), ("target", "notes/"),
+            ("description", "Notes")
+        ])));
+        assert!(controller.task(
+            "edit", Some("docs"), &fields(&[("target", "documents")])
+        ));
+        assert!(controller.task("remove", Some("photos"), &[]));
+
+        let content = fs::read_to_string(&path).unwrap();
+        for expected in ["# Settings", "# Documents",
+                         "name = \"docs\" # edited below",
+                         "target = \"documents\"", "source = \"/data/notes\""] {
+            assert!(content.contains(expected), "{}", content);
+        }
+        assert!(!content.contains("photos"), "{}", content);
+    }
+
+    #[test]
+    fn task_commands_keep_the_order_of_json_keys() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.json");
+        let content = r#"{
+  "zip_path": "backup.zip",
+  "direction": "to_zip",
+  "confirm_new": "no",
+  "confirm_overwrite": "no",
+  "confirm_delete": "no",
+  "copy_tasks": [
+    {
+      "name": "docs",
+      "source": "/data/docs",
+      "target": "",
+      "description": "Docs"
+    },
+    {
+      "name": "photos",
+      "source": "/data/photos",
+      "target": "",
+      "description": "Photos"
+    }
+  ]
+}
+"#;
+        fs::write(&path, content).unwrap();
+
+        let controller = ConfigController::new(&path, None);
+        assert!(controller.task(
+            "edit", Some("docs"), &fields(&[("target", "documents")])
+        ));
+        assert!(controller.task("remove", Some("photos"), &[]));
+
+        let photos = content.find(",\n    {\n      \"name\": \"photos\"")
+            .unwrap();
+        let expected = format!("{}\n  ]\n}}\n", &content[..photos])
+            .replace("\"target\": \"\"", "\"target\": \"documents\"");
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn task_commands_refuse_yaml_with_comments() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.yaml");
+        let content = "\
+zip_path: backup.zip # the archive
+direction: to_zip
+confirm_new: \"no\"
+confirm_overwrite: \"no\"
+confirm_delete: \"no\"
+copy_tasks: []
+";
+        fs::write(&path, content).unwrap();
+
+        let controller = ConfigController::new(&path, None);
+        assert!(!controller.task("add", None, &fields(&[
+            ("source", "/data/notes"), ("target", ""), ("description", "N")
+        ])));
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn yes_no_fields_are_normalised() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.json");
+        fs::write(&path, serde_json::to_string(&json!({
+            "zip_path": "backup.zip",
+            "direction": "to_zip",
+            "confirm_new": "no",
+            "confirm_overwrite": "no",
+            "confirm_delete": "no",
+            "copy_tasks": [
+                { "name": "docs", "source": "/data/docs",
+                  "description": "Docs", "target": "" }
+            ]
+        })).unwrap()).unwrap();
+
+        let controller = ConfigController::new(&path, None);
+        assert!(controller.task(
+            "edit", Some("docs"), &fields(&[("enabled", "false")])
+        ));
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!controller.task(
+            "edit", Some("docs"), &fields(&[("required", "maybe")])
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+        let config: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(config["copy_tasks"][0]["enabled"], "no");
+    }
+}
@@ -0,0 +1,282 @@
+use colored::*;
+
+use crate::controller::zip_creator_controller::{
+    TaskError, ZipCreatorController
+};
+use crate::model::archive_entry::{ArchiveEntry, EntryKind};
+use crate::model::config::Config;
+use crate::model::copy_task::CopyTask;
+use crate::model::path_mapping::PathMapping;
+
+
+/// The number of entries which are listed per task.
+const LISTED_ENTRIES: usize = 10;
+
+
+/// The ExplainController shows for every copy task which paths it produces in
+/// the archive and where extraction writes these paths back to. Tasks whose
+/// entries aren't restored to their sources are flagged, as are tasks which
+/// write the same path or into the path of another task.
+pub struct ExplainController {
+    /// The configuration object which holds the copy tasks.
+    config: Config
+}
+
+impl ExplainController {
+    pub fn new(config: Config) -> ExplainController {
+        ExplainController { config }
+    }
+
+    /// Explains the path mapping of all copy tasks.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all tasks round-trip and no task writes into the
+    ///   path of another one.
+    pub fn start(&self) -> bool {
+        println!("Zip path: {}\n", self.config.zip_path);
+
+        let mut asymmetric = 0;
+        for (index, task) in self.config.copy_tasks.iter().enumerate() {
+            println!("{}", format!(
+                "{}. {}", index + 1, Self::label(task)
+            ).bold());
+
+            if !Self::explain_task(task) {
+                asymmetric += 1;
+            }
+            println!();
+        }
+        let conflicts = self.check_conflicts();
+
+        if asymmetric == 0 && conflicts == 0 {
+            println!("{}", "All tasks round-trip.".green().bold());
+            return true;
+        }
+        if asymmetric > 0 {
+            println!("{}", format!(
+                "!!! {} task(s) are not restored to their sources by from_zip",
+                asymmetric
+            ).red().bold());
+        }
+        if conflicts > 0 {
+            println!("{}", format!(
+                "!!! {} pair(s) of tasks write the same paths in the archive",
+                conflicts
+            ).red().bold());
+        }
+        false
+    }
+
+    /// Returns the name and the description of a task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The copy task.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The label of the task.
+    fn label(task: &CopyTask) -> String {
+        if task.name.is_empty() {
+            task.description.clone()
+        } else {
+            format!("{} - {}", task.name, task.description)
+        }
+    }
+
+    /// Prints the pairs of tasks (including the ones which weren't selected)
+    /// which have the same path in the archive or whose paths are nested.
+    /// Their entries overwrite each other, and extraction writes the entries
+    /// of the inner task to the source of the outer task as well.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of conflicting pairs.
+    fn check_conflicts(&self) -> usize {
+        let tasks: Vec<_> = self.config.copy_tasks.iter()
+            .chain(&self.config.skipped_tasks)
+            .map(|task| (task, PathMapping::predict(task).archive_path))
+            .collect();
+
+        let mut conflicts = 0;
+        for (index, (task, path)) in tasks.iter().enumerate() {
+            for (other, other_path) in &tasks[index + 1..] {
+                let message = if path == other_path {
+                    format!("both write '{}'", path)
+                } else if other_path.starts_with(&format!("{}/", path)) {
+                    format!("'{}' is inside of '{}'", other_path, path)
+                } else if path.starts_with(&format!("{}/", other_path)) {
+                    format!("'{}' is inside of '{}'", path, other_path)
+                } else {
+                    continue;
+                };
+
+                conflicts += 1;
+                println!("{}", format!(
+                    "!!! Tasks '{}' and '{}' overlap: {}",
+                    Self::label(task), Self::label(other), message
+                ).red().bold());
+            }
+        }
+        if conflicts > 0 {
+            println!();
+        }
+        conflicts
+    }
+
+    /// Prints the archive paths of a copy task and the local paths extraction
+    /// writes them to.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The copy task.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if every entry is extracted to its source.
+    fn explain_task(task: &CopyTask) -> bool {
+        println!("   Source:  {}", task.source);
+        println!("   Target:  '{}'", task.target);
+
+        let entries = match ZipCreatorController::collect_entries(task) {
+            Ok(entries) if !entries.is_empty() => entries,
+            Ok(_) => {
+                println!("   The symlink is skipped, nothing is archived");
+                return true;
+            },
+            Err(error_type) => {
+                if !matches!(error_type, TaskError::PathNotFound) {
+                    println!("{}", format!(
+                        "   !!! {}", error_type.message()
+                    ).red().bold());
+                    return false;
+                }
+                println!("{}", "   The source doesn't exist, the paths are \
+                                predicted from the config".yellow());
+                let mapping = PathMapping::predict(task);
+                let kind = if mapping.is_dir {
+                    EntryKind::Directory
+                } else {
+                    EntryKind::File
+                };
+                vec![ArchiveEntry {
+                    zip_path: mapping.archive_path,
+                    source: mapping.local_path,
+                    kind
+                }]
+            }
+        };
+
+        // The mapping the reader finds in an archive with these entries
+        let mapping = PathMapping::from_archive(task, |zip_path| {
+            entries.iter().find_map(|entry| {
+                if entry.zip_path == zip_path {
+                    Some(entry.kind == EntryKind::Directory)
+                } else {
+                    entry.zip_path.starts_with(&format!("{}/", zip_path))
+                        .then_some(true)
+                }
+            })
+        });
+
+        let is_dir = entries[0].kind == EntryKind::Directory;
+        println!("   Archive: {}{}",
+                 entries[0].zip_path.cyan(), if is_dir { "/" } else { "" });
+        match &mapping {
+            Some(mapping) => println!(
+                "   Extract: {} -> {}",
+                mapping.archive_path, mapping.local_path.display()
+            ),
+            None => println!("{}", format!(
+                "   !!! Extraction doesn't find '{}' in the archive",
+                entries[0].zip_path
+            ).red().bold())
+        }
+
+        // Compare the extracted path of every entry with its source
+        let mut mismatches = 0;
+        for (index, entry) in entries.iter().enumerate() {
+            let extracted = mapping.as_ref().and_then(|mapping| {
+                mapping.local_path_of(&entry.zip_path)
+            });
+            let restored = extracted.as_deref() == Some(entry.source.as_path());
+            if !restored {
+                mismatches += 1;
+            }
+            if index >= LISTED_ENTRIES {
+                continue;
+            }
+
+            let line = format!(
+                "     {} -> {} -> {}",
+                entry.source.display(), entry.zip_path,
+                extracted.as_ref().map_or("(not extracted)".into(), |path| {
+                    path.display().to_string()
+                })
+            );
+            if restored {
+                println!("{}", line);
+            } else {
+                println!("{}", line.red());
+            }
+        }
+        if entries.len() > LISTED_ENTRIES {
+            println!("     ... and {} more entries",
+                     entries.len() - LISTED_ENTRIES);
+        }
+
+        if mismatches > 0 {
+            println!("{}", format!(
+                "   !!! {} of {} entries don't round-trip",
+                mismatches, entries.len()
+            ).red().bold());
+        }
+        mismatches == 0
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn colliding_and_nested_tasks_fail() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("a/c.txt"), "c").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let config = |tasks: &[(&str, &str)]| -> Config {
+            let tasks: Vec<_> = tasks.iter().map(|(source, target)| {
+                serde_json::json!({
+                    "source": root.join(source),
+                    "description": source,
+                    "target": target
+                })
+            }).collect();
+            serde_json::from_value(serde_json::json!({
+                "zip_path": root.join("archive.zip"),
+                "direction": "to_zip",
+                "confirm_new": "no",
+                "confirm_overwrite": "no",
+                "confirm_delete": "no",
+                "copy_tasks": tasks
+            })).unwrap()
+        };
+
+        let separate = config(&[("docs", ""), ("b.txt", "")]);
+        assert!(ExplainController::new(separate).start());
+
+        for tasks in [[("docs", ""), ("b.txt", "docs/")],
+                      [("a/c.txt", ""), ("b.txt", "c.txt")]] {
+            let explainer = ExplainController::new(config(&tasks));
+            assert_eq!(explainer.check_conflicts(), 1);
+            assert!(!explainer.start());
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::io::Error;
+use std::process::Command;
+use colored::*;
+
+use crate::model::config::Config;
+use crate::model::copy_task::CopyTask;
+
+
+/// The HookController runs the hook commands of a config and its copy tasks
+/// before and after an archive is created, extracted or verified.
+///
+/// The commands get the context of the run as environment variables:
+/// * `ZIPSYNC_HOOK` - "pre" or "post".
+/// * `ZIPSYNC_ARCHIVE` - The path of the archive.
+/// * `ZIPSYNC_DIRECTION` - "to_zip", "from_zip" or "verify".
+/// * `ZIPSYNC_PROFILE` - The name of the profile (empty without profile).
+/// * `ZIPSYNC_TASK`, `ZIPSYNC_SOURCE`, `ZIPSYNC_TARGET` - The name (or
+///   description), source and target of the copy task (only for the hooks
+///   of a copy task).
+/// * `ZIPSYNC_STATUS` - "success" or "failure" (only for post-hooks).
+pub struct HookController {
+    /// The hooks of the config (run before the hooks of the copy tasks).
+    pre_hooks: Vec<String>,
+
+    /// The hooks of the config (run after the hooks of the copy tasks).
+    post_hooks: Vec<String>,
+
+    /// The copy tasks with their own hooks.
+    copy_tasks: Vec<CopyTask>,
+
+    /// The path of the archive.
+    archive: String,
+
+    /// The direction of the run.
+    direction: String,
+
+    /// The name of the profile.
+    profile: String
+}
+
+impl HookController {
+    /// Creates a new `HookController` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config with the hooks and the copy tasks.
+    /// * `direction` - The direction of the run.
+    pub fn new(config: &Config, direction: &str) -> HookController {
+        HookController {
+            pre_hooks: config.pre_hooks.clone(),
+            post_hooks: config.post_hooks.clone(),
+            copy_tasks: config.copy_tasks.clone(),
+            archive: config.zip_path.clone(),
+            direction: direction.to_string(),
+            profile: config.profile.clone().unwrap_or_default()
+        }
+    }
+
+    /// Runs the pre-hooks of the config and then those of the copy tasks.
+    /// The first failing hook stops the run.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If all pre-hooks succeeded.
+    /// * `Err(Error)` - If a pre-hook cannot be run or fails.
+    pub fn run_pre_hooks(&self) -> Result<(), Error> {
+        for command in &self.pre_hooks {
+            self.run_hook(command, "pre", None, None)?;
+        }
+        for task in &self.copy_tasks {
+            for command in &task.pre_hooks {
+                self.run_hook(command, "pre", Some(task), None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the post-hooks of the copy tasks and then those of the config.
+    /// Failing post-hooks are reported, but the other hooks still run.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The path of the archive (a new snapshot has a different
+    ///   path than the template).
+    /// * `success` - Whether the run succeeded.
+    pub fn run_post_hooks(&mut self, archive: &str, success: bool) {
+        self.archive = archive.to_string();
+        let status = if success { "success" } else { "failure" };
+
+        for task in &self.copy_tasks {
+            for command in &task.post_hooks {
+                Self::report(self.run_hook(command, "post", Some(task),
+                                          Some(status)));
+            }
+        }
+        for command in &self.post_hooks {
+            Self::report(self.run_hook(command, "post", None, Some(status)));
+        }
+    }
+
+    /// Runs a hook command in the shell. Its output is shown on the terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The shell command.
+    /// * `hook` - "pre" or "post".
+    /// * `task` - The copy task of the hook (`None` for hooks of the config).
+    /// * `status` - The result of the run (only for post-hooks).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the command succeeded.
+    /// * `Err(Error)` - If the command cannot be run or fails.
+    fn run_hook(
+        &self,
+        command: &str,
+        hook: &str,
+        task: Option<&CopyTask>,
+        status: Option<&str>
+    ) -> Result<(), Error> {
+        println!("{}", format!("Running {}-hook: {}", hook, command).bold());
+
+        let mut process = if cfg!(windows) {
+            let mut process = Command::new("cmd");
+            process.args(["/C", command]);
+            process
+        } else {
+            let mut process = Command::new("sh");
+            process.args(["-c", command]);
+            process
+        };
+        process.env("ZIPSYNC_HOOK", hook)
+            .env("ZIPSYNC_ARCHIVE", &self.archive)
+            .env("ZIPSYNC_DIRECTION", &self.direction)
+            .env("ZIPSYNC_PROFILE", &self.profile);
+        if let Some(task) = task {
+            let name = if task.name.is_empty() {
+                &task.description
+            } else {
+                &task.name
+            };
+            process.env("ZIPSYNC_TASK", name)
+                .env("ZIPSYNC_SOURCE", &task.source)
+                .env("ZIPSYNC_TARGET", &task.target);
+        }
+        if let Some(status) = status {
+            process.env("ZIPSYNC_STATUS", status);
+        }
+
+        let exit_status = process.status()?;
+        if !exit_status.success() {
+            return Err(Error::other(format!(
+                "Hook '{}' failed: {}", command, exit_status
+            )));
+        }
+        Ok(())
+    }
+
+    /// Prints the error of a failed post-hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The result of the hook.
+    fn report(result: Result<(), Error>) {
+        if let Err(e) = result {
+            println!("{}", format!("!!! {}", e).yellow().bold());
+        }
+    }
+}
@@ -1,17 +1,26 @@
-use std::fs::File;
-use std::io::{BufReader, Error};
+use std::env;
+use std::path::Path;
 use colored::*;
 
+use crate::model::backup::Backup;
+use crate::model::cli_args::CliArgs;
 use crate::model::config::Config;
+use crate::model::config_loader::ConfigLoader;
+use crate::model::journal::Journal;
+use crate::controller::config_controller::ConfigController;
+use crate::controller::explain_controller::ExplainController;
+use crate::controller::hook_controller::HookController;
+use crate::controller::snapshot_controller::SnapshotController;
+use crate::controller::watch_controller::WatchController;
 use crate::controller::zip_creator_controller::ZipCreatorController;
 use crate::controller::zip_reader_controller::ZipReaderController;
+use crate::controller::zip_verify_controller::ZipVerifyController;
 
 
 /// The main controller of the application.
 pub struct MainController {
-    /// The configuration object which holds the settings for the application
-    /// and the copy tasks.
-    config: Config,
+    /// The parsed command line arguments.
+    args: CliArgs,
 }
 
 impl MainController {
@@ -22,55 +31,328 @@ impl MainController {
     /// * `MainController` - A new instance of the main controller.
     pub fn new() -> Self {
         let controller: MainController = Self {
-            config: Config::new()
+            args: CliArgs::default()
         };
         controller
     }
 
-    /// Starts the main logic of the application, including reading the config
-    /// and executing the zip operations.
-    /// TODO: implement sync back from ZIP file
-    pub fn start(&mut self) {
+    /// Starts the main logic of the application: parses the command line and
+    /// either executes a command or the zip operations of the config.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - False if the arguments or the config are invalid or a run
+    ///   failed (e.g. because of a required copy task).
+    pub fn start(&mut self) -> bool {
+        match CliArgs::parse(env::args().skip(1)) {
+            Ok(args) => self.args = args,
+            Err(e) => {
+                println!("{}", format!("{}", e).red().bold());
+                return false;
+            }
+        }
+
+        let command: Vec<&str> = self.args.command.iter()
+            .map(String::as_str)
+            .collect();
+        match command.as_slice() {
+            [] => self.run_configs(Self::run),
+            ["explain"] => self.run_configs(Self::explain),
+            ["snapshots"] => self.run_configs(|config| {
+                Self::print_profile(&config);
+                SnapshotController::new(config).list();
+                true
+            }),
+            ["restore"] => self.restore(None),
+            ["restore", selector] => self.restore(Some(selector)),
+            ["undo"] => {
+                let force = self.args.force;
+                self.run_configs(|config| Self::undo(config, force))
+            },
+            ["watch"] => {
+                let Some(configs) = self.load_configs() else {
+                    return false;
+                };
+                WatchController::new(configs).start()
+            },
+            ["config", "convert", output] => {
+                let path = self.args.config_path.as_deref();
+                let loader = ConfigLoader::new(path);
+                ConfigController::convert(loader.path(), Path::new(output))
+            },
+            ["config", "convert", input, output] => {
+                ConfigController::convert(Path::new(input), Path::new(output))
+            },
+            ["init"] => self.config_controller().init(),
+            ["task", action] => {
+                let fields = &self.args.task_fields;
+                self.config_controller().task(action, None, fields)
+            },
+            ["task", action, selector] => {
+                let fields = &self.args.task_fields;
+                self.config_controller().task(action, Some(selector), fields)
+            },
+            _ => {
+                println!("{}", format!(
+                    "Unknown command: {}\nUsage: zipsync [--config <file>] \
+                     [--profile <name> | --all]\n       \
+                     zipsync [--task <name>] [--tag <tag>] \
+                     [--exclude-tag <tag>]\n       \
+                     zipsync explain | watch [--profile <name>]\n       \
+                     zipsync snapshots\n       \
+                     zipsync restore [<number|timestamp>] [--task <name>] \
+                     [--path <path>]\n       \
+                     zipsync undo [--force]\n       \
+                     zipsync init\n       \
+                     zipsync task list | add | remove <task> | edit <task> \
+                     [--profile <name>] [--<field> <value>]\n       \
+                     zipsync config convert [<input>] <output>",
+                    self.args.command.join(" ")
+                ).red().bold());
+                false
+            }
+        }
+    }
+
+    /// Creates the controller for the commands which change the config file.
+    /// The tasks of the first profile given with `--profile` are managed.
+    ///
+    /// # Returns
+    ///
+    /// * `ConfigController` - The controller for the config file.
+    fn config_controller(&self) -> ConfigController {
+        let loader = ConfigLoader::new(self.args.config_path.as_deref());
+        let profile = self.args.profiles.first().cloned();
+        ConfigController::new(loader.path(), profile)
+    }
+
+    /// Reads the config and executes a command for every selected profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command which is executed with every config and
+    ///   returns whether it succeeded.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the config was read and the command succeeded for
+    ///   all configs (the remaining configs run after a failure as well).
+    fn run_configs(&self, command: impl Fn(Config) -> bool) -> bool {
+        let Some(configs) = self.load_configs() else {
+            return false;
+        };
+        let mut success = true;
+        for config in configs {
+            success &= command(config);
+        }
+        success
+    }
+
+    /// Reads the config and returns the configs of the selected profiles
+    /// with the copy tasks selected by `--task`, `--tag` and `--exclude-tag`.
+    /// Tasks which don't run on this machine are skipped with a note, as are
+    /// configs without any selected task.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<Config>>` - The configs (`None` if the config file
+    ///   cannot be read).
+    fn load_configs(&self) -> Option<Vec<Config>> {
         // Read config file
-        match Self::read_config() {
-            Ok(config) => {
+        let loader = ConfigLoader::new(self.args.config_path.as_deref());
+        match loader.load(&self.args.profiles, self.args.all_profiles) {
+            Ok(mut configs) => {
                 println!("=== {} ===", "ZipSync".bold());
+                if let Some(path) = loader.machine_override_path() {
+                    println!("Machine override: {}", path.display());
+                }
 
-                // Check copy direction
-                if config.direction == "to_zip" {
-                    // Copy files to the ZIP archive
-                    println!("Zip path:  {}", self.config.zip_path);
-                    println!("Direction: {}\n", self.config.direction);
-
-                    let mut zip_creator = ZipCreatorController::new(config);
-                    zip_creator.start();
-                } else if config.direction == "from_zip" {
-                    // Copy files from the ZIP archive to the paths in the config
-                    let mut zip_reader = ZipReaderController::new(config);
-                    zip_reader.start();
-                } else {
-                    println!("{}", format!("Unknown copy direction: {}",
-                    self.config.direction).red().bold());
+                let machine = ConfigLoader::machine_name();
+                for config in configs.iter_mut() {
+                    let inactive = config.skip_inactive_tasks(
+                        machine.as_deref()
+                    );
+                    for (task, reason) in inactive {
+                        println!("{}", format!(
+                            "Skipping task '{}': {}", task.description, reason
+                        ).yellow());
+                    }
+                    config.select_tasks(&self.args.tasks, &self.args.tags,
+                                        &self.args.exclude_tags);
                 }
+                configs.retain(|config| {
+                    let selected = !config.copy_tasks.is_empty()
+                        || config.skipped_tasks.is_empty();
+                    if !selected {
+                        let profile = config.profile.as_deref()
+                            .map_or(String::new(), |p| format!(" ({})", p));
+                        println!("{}", format!(
+                            "No copy task selected{}, skipped", profile
+                        ).yellow());
+                    }
+                    selected
+                });
+                Some(configs)
             }
             Err(e) => {
-                println!("Error reading JSON file: {}", e);
+                println!("Error reading config file: {}", e);
+                None
             }
         }
     }
 
-    /// Reads the configuration file and returns a `Config` instance.
+    /// Restores a snapshot of the archive of every selected profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The number or timestamp of the snapshot (the newest if
+    ///   `None`).
     ///
     /// # Returns
     ///
-    /// * `Ok(Config)` - Configuration object parsed from the JSON file.
-    /// * `Err(Error)` - If the file cannot be read or parsed.
-    fn read_config() -> Result<Config, Error> {
-        let file_path = "data/config.json";
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
-        let mut config: Config = serde_json::from_reader(reader)?;
-        config.clean();
-        Ok(config)
+    /// * `bool` - True if all snapshots were restored.
+    fn restore(&self, selector: Option<&str>) -> bool {
+        self.run_configs(|config| {
+            Self::print_profile(&config);
+            SnapshotController::new(config).restore(
+                selector, self.args.path.as_deref()
+            )
+        })
     }
-}
\ No newline at end of file
+
+    /// Prints the name of the profile of a config (if it has one).
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config (of a single profile).
+    fn print_profile(config: &Config) {
+        if let Some(profile) = &config.profile {
+            println!("\n{}", format!("--- Profile: {} ---", profile).bold());
+        }
+    }
+
+    /// Executes the zip operation of a config depending on its direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config (of a single profile).
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the zip operation succeeded.
+    fn run(mut config: Config) -> bool {
+        Self::print_profile(&config);
+
+        // Read and verify the newest snapshot
+        if config.direction != "to_zip"
+        && let Err(e) = config.use_snapshot(None) {
+            println!("{}", format!("!!! {}", e).red().bold());
+            return false;
+        }
+
+        let direction = config.direction.as_str();
+        if !matches!(direction, "to_zip" | "from_zip" | "verify") {
+            println!("{}", format!("Unknown copy direction: {}",
+            config.direction).red().bold());
+            return false;
+        }
+
+        // A failing pre-hook stops the run before anything is written
+        let mut hooks = HookController::new(&config, &config.direction);
+        if let Err(e) = hooks.run_pre_hooks() {
+            println!("{}", format!("!!! Run aborted: {}", e).red().bold());
+            return false;
+        }
+
+        // Check copy direction
+        let archive = config.zip_path.clone();
+        let (archive, success) = if config.direction == "to_zip" {
+            // Copy files to the ZIP archive
+            println!("Zip path:  {}", config.zip_path);
+            println!("Direction: {}\n", config.direction);
+
+            let mut zip_creator = ZipCreatorController::new(config);
+            let success = zip_creator.start();
+            (zip_creator.config().zip_path.clone(), success)
+        } else if config.direction == "from_zip" {
+            // Copy files from the ZIP archive to the paths in the config
+            let mut zip_reader = ZipReaderController::new(config);
+            (archive, zip_reader.start())
+        } else {
+            // Compare the content of the ZIP archive with the sources
+            let mut zip_verifier = ZipVerifyController::new(config);
+            (archive, zip_verifier.start())
+        };
+
+        hooks.run_post_hooks(&archive, success);
+        success
+    }
+
+    /// Explains the path mapping of the copy tasks of a config.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config (of a single profile).
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all tasks round-trip and don't overlap.
+    fn explain(config: Config) -> bool {
+        Self::print_profile(&config);
+        ExplainController::new(config).start()
+    }
+
+    /// Undoes the last `from_zip` run of a config: the replaced files are
+    /// restored from the backup and the created files are removed. The
+    /// journal of an interrupted run is discarded, as the run is rolled back.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config (of a single profile).
+    /// * `force` - Whether files changed after the run are restored anyway.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if there was nothing to undo or the backup was
+    ///   restored.
+    fn undo(config: Config, force: bool) -> bool {
+        Self::print_profile(&config);
+
+        let root = config.backup_root();
+        let backup = match Backup::latest(&root) {
+            Ok(Some(backup)) => backup,
+            Ok(None) => {
+                println!("No backup found in '{}'", root.display());
+                return true;
+            },
+            Err(e) => {
+                println!("{}", format!(
+                    "!!! Backups cannot be read: {}", e
+                ).red().bold());
+                return false;
+            }
+        };
+
+        println!("Undoing: {}", backup.directory.display());
+        let result = backup.restore(force).and_then(|count| {
+            if let Some(journal) = Journal::open(&root)? {
+                journal.finish()?;
+            }
+            Ok(count)
+        });
+        match result {
+            Ok(count) => {
+                println!("{}", format!(
+                    "{} path(s) restored", count
+                ).green().bold());
+                true
+            },
+            Err(e) => {
+                println!("{}", format!(
+                    "!!! Backup could not be restored: {}", e
+                ).red().bold());
+                false
+            }
+        }
+    }
+}
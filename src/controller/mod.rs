@@ -1,3 +1,9 @@
+pub mod config_controller;
+pub mod explain_controller;
+pub mod hook_controller;
 pub mod main_controller;
+pub mod snapshot_controller;
+pub mod watch_controller;
 pub mod zip_creator_controller;
-pub mod zip_reader_controller;
\ No newline at end of file
+pub mod zip_reader_controller;
+pub mod zip_verify_controller;
\ No newline at end of file
@@ -0,0 +1,90 @@
+use std::fs;
+use colored::*;
+
+use crate::controller::hook_controller::HookController;
+use crate::controller::zip_reader_controller::ZipReaderController;
+use crate::model::config::Config;
+use crate::model::snapshot::Snapshot;
+
+
+/// The SnapshotController lists the snapshots of an archive and restores a
+/// chosen snapshot (or a part of it).
+pub struct SnapshotController {
+    /// The configuration object with the snapshot template and the copy
+    /// tasks.
+    config: Config
+}
+
+impl SnapshotController {
+    pub fn new(config: Config) -> SnapshotController {
+        SnapshotController { config }
+    }
+
+    /// Lists the snapshots of the archive, the newest first. The numbers can
+    /// be used to select a snapshot for `restore`.
+    pub fn list(&self) {
+        let Some(template) = &self.config.snapshot_template else {
+            println!("'{}' is not a snapshot template", self.config.zip_path);
+            return;
+        };
+
+        let snapshots = match Snapshot::list(template) {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                println!("{}", format!(
+                    "!!! Snapshots cannot be listed: {}", e
+                ).red().bold());
+                return;
+            }
+        };
+        if snapshots.is_empty() {
+            println!("No snapshots found for '{}'", template);
+        }
+
+        for (index, snapshot) in snapshots.iter().enumerate() {
+            let size = fs::metadata(&snapshot.path)
+                .map_or(0, |metadata| metadata.len());
+            println!(
+                "{:>3}. {}  {:>12} bytes  {}",
+                index + 1, snapshot.timestamp().bold(), size,
+                snapshot.path.display()
+            );
+        }
+    }
+
+    /// Extracts a snapshot to the sources of the (selected) copy tasks (like
+    /// `from_zip`).
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The number or timestamp of the snapshot (the newest if
+    ///   `None`).
+    /// * `path` - The path in the archive which is restored (all if `None`).
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the snapshot was restored.
+    pub fn restore(mut self, selector: Option<&str>, path: Option<&str>)
+    -> bool {
+        if let Err(e) = self.config.use_snapshot(selector) {
+            println!("{}", format!("!!! {}", e).red().bold());
+            return false;
+        }
+
+        let mut hooks = HookController::new(&self.config, "from_zip");
+        if let Err(e) = hooks.run_pre_hooks() {
+            println!("{}", format!("!!! Restore aborted: {}", e).red().bold());
+            return false;
+        }
+
+        println!("Restoring: {}\n", self.config.zip_path);
+        let archive = self.config.zip_path.clone();
+        let mut zip_reader = ZipReaderController::new(self.config);
+        if let Some(path) = path {
+            zip_reader.restrict_to_path(path);
+        }
+        let success = zip_reader.start();
+        hooks.run_post_hooks(&archive, success);
+        success
+    }
+}
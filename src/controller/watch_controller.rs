@@ -0,0 +1,332 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+use colored::*;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::controller::hook_controller::HookController;
+use crate::controller::zip_creator_controller::ZipCreatorController;
+use crate::model::config::Config;
+
+
+/// The time without further changes before the archive is updated (if the
+/// config doesn't set `watch_debounce_ms`).
+const DEFAULT_DEBOUNCE_MS: u64 = 2000;
+
+
+/// The WatchController monitors the sources of all copy tasks and updates
+/// the archives after changes. Only the changed files are read and
+/// compressed again. Only configs with the direction "to_zip" are watched.
+/// The hooks of a config run around every update of its archive.
+pub struct WatchController {
+    /// One creator per config; they remember the files of the previous
+    /// update to copy the unchanged ones from its archive.
+    creators: Vec<ZipCreatorController>,
+
+    /// The time without further changes before the archives are updated.
+    debounce: Duration
+}
+
+impl WatchController {
+    pub fn new(configs: Vec<Config>) -> WatchController {
+        // Watching would overwrite the archive of the other directions
+        let configs: Vec<Config> = configs.into_iter().filter(|config| {
+            let watched = config.direction == "to_zip";
+            if !watched {
+                println!("{}", format!(
+                    "!!! Not watching '{}': direction is '{}' (only \
+                     \"to_zip\" is watched)",
+                    config.zip_path, config.direction
+                ).yellow().bold());
+            }
+            watched
+        }).collect();
+
+        let debounce = configs.iter()
+            .filter_map(|config| config.watch_debounce_ms)
+            .max()
+            .unwrap_or(DEFAULT_DEBOUNCE_MS);
+
+        WatchController {
+            creators: configs.into_iter().map(ZipCreatorController::new)
+                .collect(),
+            debounce: Duration::from_millis(debounce)
+        }
+    }
+
+    /// Creates the archives and updates them whenever a source changes,
+    /// until the process is stopped.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - False if there is nothing to watch or the sources cannot
+    ///   be watched.
+    pub fn start(&mut self) -> bool {
+        if self.creators.is_empty() {
+            println!("{}", "!!! No config with the direction \"to_zip\" to \
+                             watch".red().bold());
+            return false;
+        }
+
+        for creator in self.creators.iter_mut() {
+            Self::update(creator, &[]);
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                println!("{}", format!(
+                    "!!! Sources cannot be watched: {}", e
+                ).red().bold());
+                return false;
+            }
+        };
+        let watched = self.watch_sources(&mut watcher);
+        println!("{}", format!(
+            "\nWatching {} path(s) for changes (Ctrl+C to stop)", watched
+        ).bold());
+
+        while let Some(changed_paths) = self.next_changes(&receiver) {
+            for creator in self.creators.iter_mut() {
+                let changed = Self::changed_sources(creator.config(),
+                                                    &changed_paths);
+                if !changed.is_empty() {
+                    println!("\n{}", format!(
+                        "{} change(s) detected, updating '{}'",
+                        changed.len(), creator.config().zip_path
+                    ).bold());
+                    Self::update(creator, &changed);
+                }
+            }
+
+            // Changes made during the updates (e.g. by the hooks) would
+            // trigger the next update right away
+            while receiver.try_recv().is_ok() {}
+        }
+        true
+    }
+
+    /// Updates the archive of a creator between the pre- and post-hooks of
+    /// its config, like a normal run. A failing pre-hook skips the update.
+    ///
+    /// # Arguments
+    ///
+    /// * `creator` - The creator of the archive.
+    /// * `changed` - The changed sources.
+    fn update(creator: &mut ZipCreatorController, changed: &[PathBuf]) {
+        let mut hooks = HookController::new(creator.config(), "to_zip");
+        if let Err(e) = hooks.run_pre_hooks() {
+            println!("{}", format!(
+                "!!! Update of '{}' skipped: {}", creator.config().zip_path, e
+            ).red().bold());
+            return;
+        }
+
+        let success = creator.update(changed);
+        hooks.run_post_hooks(&creator.config().zip_path, success);
+    }
+
+    /// Registers the sources of all copy tasks with the watcher. Directories
+    /// are watched recursively; for files, the parent directory is watched
+    /// so files which are replaced (e.g. by editors) are noticed as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `watcher` - The watcher.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of watched sources.
+    fn watch_sources(&self, watcher: &mut RecommendedWatcher) -> usize {
+        let mut watched = 0;
+        for creator in &self.creators {
+            for task in &creator.config().copy_tasks {
+                let source = Path::new(&task.source);
+                let result = if source.is_dir() {
+                    watcher.watch(source, RecursiveMode::Recursive)
+                } else if source.exists() {
+                    let parent = source.parent()
+                        .filter(|parent| !parent.as_os_str().is_empty())
+                        .unwrap_or(Path::new("."));
+                    watcher.watch(parent, RecursiveMode::NonRecursive)
+                } else {
+                    println!("{}", format!(
+                        "!!! Source not found, not watched: {}", task.source
+                    ).yellow().bold());
+                    continue;
+                };
+
+                match result {
+                    Ok(()) => watched += 1,
+                    Err(e) => println!("{}", format!(
+                        "!!! Source cannot be watched: {} ({})", task.source, e
+                    ).yellow().bold())
+                }
+            }
+        }
+        watched
+    }
+
+    /// Waits for the next changes. After the first change, further changes
+    /// are collected until no change happened for the debounce time.
+    ///
+    /// # Arguments
+    ///
+    /// * `receiver` - The receiver of the watcher events.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<PathBuf>>` - The changed paths (`None` if the watcher
+    ///   stopped).
+    fn next_changes(&self, receiver: &Receiver<notify::Result<Event>>)
+    -> Option<Vec<PathBuf>> {
+        let mut changed_paths = Vec::new();
+        Self::collect_paths(receiver.recv().ok()?, &mut changed_paths);
+
+        loop {
+            match receiver.recv_timeout(self.debounce) {
+                Ok(event) => Self::collect_paths(event, &mut changed_paths),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return None
+            }
+        }
+
+        changed_paths.sort();
+        changed_paths.dedup();
+        Some(changed_paths)
+    }
+
+    /// Adds the paths of a watcher event to the changed paths. Pure access
+    /// events are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The watcher event.
+    /// * `changed_paths` - The changed paths.
+    fn collect_paths(
+        event: notify::Result<Event>, changed_paths: &mut Vec<PathBuf>
+    ) {
+        match event {
+            Ok(event) if !event.kind.is_access() => {
+                changed_paths.extend(event.paths);
+            },
+            Ok(_) => {},
+            Err(e) => println!("{}", format!(
+                "!!! Watch error: {}", e
+            ).yellow().bold())
+        }
+    }
+
+    /// Returns the changed paths which belong to the copy tasks of a config,
+    /// relative to the sources as they are written in the config. Changes of
+    /// the archive itself are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config with the archive and the copy tasks.
+    /// * `changed_paths` - The changed (absolute) paths.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<PathBuf>` - The changed paths within the sources.
+    fn changed_sources(config: &Config, changed_paths: &[PathBuf])
+    -> Vec<PathBuf> {
+        let archive = Self::absolute(Path::new(&config.zip_path));
+        let mut changed = Vec::new();
+
+        for path in changed_paths {
+            if path.starts_with(&archive) {
+                continue;
+            }
+
+            for task in &config.copy_tasks {
+                let source = Path::new(&task.source);
+                let absolute_source = Self::absolute(source);
+                if let Ok(relative) = path.strip_prefix(&absolute_source) {
+                    changed.push(source.join(relative));
+                }
+            }
+        }
+        changed
+    }
+
+    /// Returns the absolute path of a path (as the watcher reports it, so
+    /// symbolic links are not resolved).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path.
+    ///
+    /// # Returns
+    ///
+    /// * `PathBuf` - The absolute path.
+    fn absolute(path: &Path) -> PathBuf {
+        std::path::absolute(path).unwrap_or_else(|_e| path.to_path_buf())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_to_zip_configs_are_watched() {
+        let configs = ["to_zip", "from_zip", "verify"].map(|direction| {
+            serde_json::from_value(serde_json::json!({
+                "zip_path": format!("{}.zip", direction),
+                "direction": direction,
+                "confirm_new": "no",
+                "confirm_overwrite": "no",
+                "confirm_delete": "no",
+                "copy_tasks": []
+            })).unwrap()
+        });
+
+        let mut watch = WatchController::new(configs[1..].to_vec());
+        assert!(watch.creators.is_empty());
+        assert!(!watch.start());
+
+        let watch = WatchController::new(configs.to_vec());
+        let watched: Vec<_> = watch.creators.iter()
+            .map(|creator| creator.config().direction.as_str())
+            .collect();
+        assert_eq!(watched, ["to_zip"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks_run_around_every_update() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let log = root.join("log");
+        std::fs::write(root.join("a.txt"), "a").unwrap();
+
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "zip_path": root.join("archive.zip"),
+            "direction": "to_zip",
+            "confirm_new": "no",
+            "confirm_overwrite": "no",
+            "confirm_delete": "no",
+            "pre_hooks": [format!("echo pre >> '{}'", log.display())],
+            "post_hooks": [format!(
+                "echo \"post $ZIPSYNC_STATUS\" >> '{}'", log.display()
+            )],
+            "copy_tasks": [{
+                "source": root.join("a.txt"),
+                "description": "a",
+                "target": ""
+            }]
+        })).unwrap();
+
+        let mut watch = WatchController::new(vec![config]);
+        for _update in 0..2 {
+            WatchController::update(&mut watch.creators[0], &[]);
+        }
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "pre\npost success\npre\npost success\n"
+        );
+    }
+}
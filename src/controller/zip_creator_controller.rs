@@ -1,13 +1,38 @@
-use std::fs::{self, File, Metadata};
-use std::path::Path;
-use std::io::{BufReader, Error, BufWriter, Read, Write, Seek};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::io::{Error, ErrorKind, Read};
 use std::collections::{HashSet, HashMap};
-use zip::write::FileOptions;
-use zip::{CompressionMethod, ZipWriter, ZipArchive};
+use zip::CompressionMethod;
 use colored::*;
+use rayon::prelude::*;
+use rayon::ThreadPool;
 
+use crate::backend::{self, ArchiveWriter, FileEncoder};
+use crate::model::archive_entry::{ArchiveEntry, EntryKind};
+use crate::model::compression::Compression;
 use crate::model::config::Config;
 use crate::model::copy_task::CopyTask;
+use crate::model::file_metadata::FileMetadata;
+use crate::model::manifest::{
+    ContentHasher, Manifest, ManifestEntry, MANIFEST_PATH
+};
+use crate::model::password_source::PasswordSource;
+use crate::model::path_mapping::PathMapping;
+use crate::model::snapshot::Snapshot;
+
+
+/// The number of entries which are compressed in parallel before they are
+/// appended to the archive.
+const PARALLEL_BATCH_SIZE: usize = 64;
+
+/// The total size of the files which are compressed in parallel, so a batch
+/// of large files doesn't have to fit into memory at once.
+const MAX_BATCH_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Files above this size are not read into memory, but streamed into the
+/// archive one after another.
+const STREAM_THRESHOLD: u64 = 8 * 1024 * 1024;
 
 
 pub struct ZipCreatorController {
@@ -16,61 +41,516 @@ pub struct ZipCreatorController {
     config: Config,
 
     /// A HashMap for storing items that were missing during ZIP creation.
-    missing_items: HashMap<String, String>
+    missing_items: HashMap<String, String>,
+
+    /// The directories which were already added to the ZIP archive.
+    added_directories: HashSet<String>,
+
+    /// The manifest which is embedded in the ZIP archive (if enabled).
+    manifest: Option<Manifest>,
+
+    /// The password for AES encryption (if enabled).
+    password: Option<String>,
+
+    /// The files of the archive of the previous update (by their path within
+    /// the archive), which are copied from it if they didn't change (only
+    /// in watch mode).
+    written: Option<HashMap<String, WrittenFile>>,
+
+    /// The files written by the current run, which replace `written` if the
+    /// run succeeds.
+    writing: HashMap<String, WrittenFile>,
+
+    /// The archive written by the previous update (only in watch mode).
+    last_archive: Option<PathBuf>
 }
 
 impl ZipCreatorController {
     pub fn new(config: Config) -> ZipCreatorController {
         ZipCreatorController {
             config,
-            missing_items: HashMap::new()
+            missing_items: HashMap::new(),
+            added_directories: HashSet::new(),
+            manifest: None,
+            password: None,
+            written: None,
+            writing: HashMap::new(),
+            last_archive: None
         }
     }
 
     /// Starts the ZIP creation process.
-    pub fn start(&mut self) {
-        self.create_zip().unwrap();
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the archive was created and no required task
+    ///   failed.
+    pub fn start(&mut self) -> bool {
+        match self.create_zip() {
+            Ok(()) if self.failed_required_tasks().is_empty() => true,
+            Ok(()) => {
+                println!("{}", format!(
+                    "!!! Required task(s) failed: {}",
+                    self.failed_required_tasks().join(", ")
+                ).red().bold());
+                false
+            },
+            Err(e) => {
+                println!("{}", format!(
+                    "!!! Archive could not be created: {}", e
+                ).red().bold());
+                false
+            }
+        }
+    }
+
+    /// Returns the config of the controller.
+    ///
+    /// # Returns
+    ///
+    /// * `&Config` - The config with the archive and the copy tasks.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Recreates the archive and only reads and compresses the files which
+    /// changed since the previous update. All other files are copied from
+    /// the archive of the previous update (see `ArchiveWriter::
+    /// copy_unchanged`).
+    ///
+    /// # Arguments
+    ///
+    /// * `changed_paths` - The paths which changed (files or directories);
+    ///   files with a different modification time or size are detected
+    ///   as well.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the archive was updated and no required task
+    ///   failed.
+    pub fn update(&mut self, changed_paths: &[PathBuf]) -> bool {
+        let written = self.written.get_or_insert_with(HashMap::new);
+        written.retain(|_zip_path, file| {
+            !changed_paths.iter().any(|changed| {
+                file.source.starts_with(changed)
+            })
+        });
+
+        self.start()
     }
 
-    /// Creates a ZIP archive including the folders and files specified in
-    /// the config.
+    /// Creates an archive (ZIP or tar, depending on the format of the config)
+    /// including the folders and files specified in the config.
+    ///
+    /// The archive is written to a temporary file next to it ("<zip_path>
+    /// .partial"), which only replaces the existing archive if the run wasn't
+    /// aborted. A directory mirror is written in place.
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - If the ZIP file was successfully created.
-    /// * `Err(zip::result::ZipError)` - If an error occurs during ZIP file
-    ///   creation.
-    fn create_zip(&mut self) -> zip::result::ZipResult<()> {
+    /// * `Ok(())` - If the archive was successfully created (or the run was
+    ///   aborted because of a required task).
+    /// * `Err(Error)` - If an error occurs during archive creation.
+    fn create_zip(&mut self) -> Result<(), Error> {
+        self.missing_items.clear();
+        self.added_directories.clear();
+        self.writing.clear();
+
+        // Read the password before the existing archive is overwritten
+        match self.config.encryption.as_str() {
+            "" => {},
+            "aes256" if self.password.is_some() => {},
+            "aes256" => {
+                self.password = Some(PasswordSource::read_password(
+                    self.config.password_source.as_ref(), true
+                )?);
+            },
+            other => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown encryption: {}", other)
+                ));
+            }
+        }
+
+        // The required sources are collected before anything is written, so
+        // a missing source leaves the existing archive untouched. A mirror
+        // cannot be replaced as a whole, so "abort" checks them first, too.
+        let policy = self.config.error_policy()?.to_string();
+        let mirror = backend::ArchiveFormat::from_config(&self.config)?
+            == backend::ArchiveFormat::Dir;
+        let mut collected = HashMap::new();
+        if policy == "abort_without_writing" || (policy == "abort" && mirror) {
+            for (index, task) in self.config.copy_tasks.clone().iter()
+                .enumerate().filter(|(_index, task)| task.is_required()) {
+                match Self::collect_entries(task) {
+                    Ok(entries) => { collected.insert(index, entries); },
+                    Err(error_type) => {
+                        self.store_missing(task.clone(), error_type);
+                    }
+                }
+            }
+
+            let failed = self.failed_required_tasks();
+            if !failed.is_empty() {
+                return Err(Error::other(format!(
+                    "Required task(s) failed: {}", failed.join(", ")
+                )));
+            }
+        }
+
+        // The entries of the tasks which weren't selected are taken from the
+        // previous archive (or the newest snapshot)
+        let previous = self.previous_archive()?;
+
+        // A snapshot template results in a new archive for every run
+        if let Some(template) = &self.config.snapshot_template {
+            self.config.zip_path = Snapshot::new_path(template)?;
+        }
+
         let zip_file_path = self.config.zip_path.clone(); // Path of the .zip
-        let file = File::create(&zip_file_path)?; // Create the file
-        let writer = BufWriter::new(file);        // For efficient file writing
-        let mut zip = ZipWriter::new(writer);     // ZIP archive writer
+        let partial = (!mirror).then(|| format!("{}.partial", zip_file_path));
+        let written_path = partial.as_deref().unwrap_or(&zip_file_path);
+
+        let result = self.write_archive(
+            written_path, previous.as_deref(), &policy, collected
+        );
+        let completed = match result {
+            Ok(completed) => completed,
+            Err(e) => {
+                if let Some(partial) = &partial {
+                    let _ = fs::remove_file(partial);
+                }
+                return Err(e);
+            }
+        };
+
+        if !completed {
+            let message = match &partial {
+                Some(partial) => {
+                    fs::remove_file(partial)?;
+                    format!("!!! '{}' was not replaced", zip_file_path)
+                },
+                None => format!(
+                    "!!! '{}' was only partially updated", zip_file_path
+                )
+            };
+            println!("{}", message.red().bold());
+            return Ok(());
+        }
+        if let Some(partial) = &partial {
+            fs::rename(partial, &zip_file_path)?;
+        }
+
+        // The next update copies the unchanged files from this archive
+        if self.written.is_some() {
+            self.written = Some(std::mem::take(&mut self.writing));
+            self.last_archive = Some(PathBuf::from(&zip_file_path));
+        }
+        self.display_task_completed_message(zip_file_path.as_str());
+
+        // An incomplete snapshot doesn't replace older ones
+        if !self.failed_required_tasks().is_empty() {
+            return Ok(());
+        }
+        self.prune_snapshots()
+    }
+
+    /// Writes the entries of the previous archive and of all copy tasks to a
+    /// new archive. With the `on_error` policy "abort" (or
+    /// "abort_without_writing"), the remaining tasks are skipped after a
+    /// required task failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path the archive is written to.
+    /// * `previous` - The archive the entries of the tasks which weren't
+    ///   selected are copied from (if any).
+    /// * `policy` - The `on_error` policy.
+    /// * `collected` - The entries of the tasks which were already collected
+    ///   (by the index of the task).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - False if the run was aborted because of a required
+    ///   task.
+    /// * `Err(Error)` - If an error occurs during archive creation.
+    fn write_archive(
+        &mut self, path: &str, previous: Option<&Path>, policy: &str,
+        mut collected: HashMap<usize, Vec<ArchiveEntry>>
+    ) -> Result<bool, Error> {
+        let mut writer = backend::create_writer(
+            &self.config, path, self.password.clone()
+        )?;
+
+        // Worker pool for compressing the files (0 = number of CPU cores)
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.config.threads.unwrap_or(0))
+            .build()
+            .map_err(Error::other)?;
+
+        if self.config.manifest == "yes" {
+            self.manifest = Some(Manifest::new(&self.config.manifest_hash));
+        }
+
+        if let Some(previous) = previous {
+            self.copy_previous_entries(writer.as_mut(), previous)?;
+        }
+
+        if let (Some(written), Some(archive)) =
+            (&self.written, &self.last_archive)
+        && !written.is_empty() {
+            writer.copy_unchanged_from(archive)?;
+        }
 
         // Loop copy tasks
-        for task in self.config.copy_tasks.clone().iter() {
-            let source_path = Path::new(&task.source);
+        let mut completed = true;
+        for (index, task) in self.config.copy_tasks.clone().iter().enumerate()
+        {
             println!("Processing: {}", &task.source);
 
-            // Check if source exists; if not skip iteration
-            if !source_path.exists() {
-                self.store_missing(task.clone(), TaskError::PathNotFound);
-                continue;
-            }
+            // Collect the entries of the task (unless this already happened)
+            let entries = match collected.remove(&index) {
+                Some(entries) => Ok(entries),
+                None => Self::collect_entries(task)
+            };
+            match entries {
+                Ok(entries) => {
+                    self.add_entries_to_zip(
+                        writer.as_mut(), &pool, task, entries
+                    );
 
-            // Get metadata of the source path and add item to zip
-            let metadata = match fs::metadata(source_path) {
-                Ok(meta) => meta,
-                Err(_e) => {
-                    self.store_missing(task.clone(), TaskError::MetadataError);
-                    continue;
+                    // A mirror removes the files which were deleted from the
+                    // source (only if the whole task was written)
+                    if !self.missing_items.contains_key(&task.source)
+                    && let Some(mapping) = PathMapping::from_source(task) {
+                        let kept = self.paths_of_other_tasks(index);
+                        writer.prune_below(&mapping.archive_path, &kept);
+                    }
+                },
+                Err(error_type) => {
+                    self.store_missing(task.clone(), error_type);
                 }
+            }
+
+            if policy != "continue" && task.is_required()
+            && self.missing_items.contains_key(&task.source) {
+                println!("{}", format!(
+                    "!!! Required task failed, skipping the remaining \
+                     tasks: {}", task.source
+                ).red().bold());
+                completed = false;
+                break;
+            }
+        }
+
+        // Embed the manifest and write all other zip-structures
+        self.write_manifest(writer.as_mut())?;
+        writer.finalize()?;
+        Ok(completed)
+    }
+
+    /// Returns the sources of the required copy tasks which failed in the
+    /// last run.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - The sources of the failed required tasks.
+    fn failed_required_tasks(&self) -> Vec<String> {
+        self.config.copy_tasks.iter()
+            .filter(|task| task.is_required()
+                && self.missing_items.contains_key(&task.source))
+            .map(|task| task.source.clone())
+            .collect()
+    }
+
+    /// Returns the archive the entries of the tasks which weren't selected
+    /// are taken from: the existing archive, or the newest snapshot if the
+    /// `zip_path` is a snapshot template. Directory mirrors keep these
+    /// entries anyway.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(PathBuf))` - The path of the previous archive.
+    /// * `Ok(None)` - If all tasks are selected or there is no previous
+    ///   archive.
+    /// * `Err(Error)` - If the format or the snapshots cannot be read.
+    fn previous_archive(&self) -> Result<Option<PathBuf>, Error> {
+        if self.config.skipped_tasks.is_empty()
+        || backend::ArchiveFormat::from_config(&self.config)?
+            == backend::ArchiveFormat::Dir {
+            return Ok(None);
+        }
+
+        let path = match &self.config.snapshot_template {
+            Some(template) => Snapshot::list(template)?.into_iter()
+                .next()
+                .map(|snapshot| snapshot.path),
+            None => Some(PathBuf::from(&self.config.zip_path))
+        };
+        Ok(path.filter(|path| path.is_file()))
+    }
+
+    /// Copies the entries of the tasks which weren't selected from the
+    /// previous archive, so a run of some tasks doesn't remove the others
+    /// from the archive. Their manifest entries are kept as well. Files are
+    /// copied without recompressing them if the format allows it, otherwise
+    /// they are streamed into the new archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `previous` - The path of the previous archive.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the entries were copied.
+    /// * `Err(Error)` - If the previous archive cannot be read.
+    fn copy_previous_entries(
+        &mut self, writer: &mut dyn ArchiveWriter, previous: &Path
+    ) -> Result<(), Error> {
+        let mut config = self.config.clone();
+        config.zip_path = previous.to_string_lossy().to_string();
+        // The password of the new archive is tried first, so it is usually
+        // only read once
+        let mut reader = backend::open_reader_with_password(
+            &config, self.password.clone()
+        )?;
+        writer.copy_unchanged_from(previous)?;
+
+        let mut previous_manifest: Option<Manifest> = None;
+        let mut kept_files = HashSet::new();
+        reader.read_entries(&mut |entry, content| {
+            if entry.name == MANIFEST_PATH {
+                previous_manifest = serde_json::from_reader(content).ok();
+                return Ok(());
+            }
+            let Some(task) = Self::skipped_task_of(&self.config, &entry.name)
+            else {
+                return Ok(());
             };
-            self.add_item_to_zip(&mut zip, task.clone(), metadata);
+
+            println!("Keeping: {}", entry.name);
+            self.add_parent_directories(writer, &entry.name)?;
+            match &entry.kind {
+                EntryKind::Directory => {
+                    let dir_name = format!("{}/", entry.name);
+                    if self.added_directories.insert(dir_name) {
+                        writer.write_directory(&entry.name, entry.metadata)?;
+                    }
+                },
+                EntryKind::Symlink(target) => {
+                    writer.write_symlink(&entry.name, target)?;
+                },
+                EntryKind::File => {
+                    if !writer.copy_unchanged(&entry.name)? {
+                        let compression = Compression::from_settings(
+                            &self.config, &task
+                        ).unwrap_or(Compression {
+                            method: CompressionMethod::Deflated,
+                            level: None
+                        });
+                        writer.write_stream(
+                            &entry.name, content, entry.size, compression,
+                            entry.metadata
+                        )?;
+                    }
+                    kept_files.insert(entry.name.clone());
+                }
+            }
+            Ok(())
+        })?;
+
+        // Entries hashed with another algorithm cannot be kept
+        if let (Some(manifest), Some(previous_manifest)) =
+            (self.manifest.as_mut(), previous_manifest)
+        && previous_manifest.hash_algorithm == manifest.hash_algorithm {
+            manifest.entries.extend(previous_manifest.entries.into_iter()
+                .filter(|entry| kept_files.contains(&entry.path)));
         }
 
-        // Finish the last file and write all other zip-structures
-        zip.finish()?;
-        self.display_task_completed_message(zip_file_path.as_str());
+        Ok(())
+    }
+
+    /// Returns the paths within the archive of all copy tasks (including the
+    /// ones which weren't selected) except one.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the excluded task in `copy_tasks`.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - The paths of the other tasks.
+    fn paths_of_other_tasks(&self, index: usize) -> Vec<String> {
+        let copy_tasks = self.config.copy_tasks.iter().enumerate()
+            .filter(|(other, _task)| *other != index)
+            .map(|(_other, task)| task);
+        copy_tasks.chain(&self.config.skipped_tasks)
+            .map(|task| PathMapping::predict(task).archive_path)
+            .collect()
+    }
+
+    /// Returns the task which wasn't selected an entry of the previous
+    /// archive belongs to. Entries which also belong to a selected task are
+    /// written by that task.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration with the selected and skipped tasks.
+    /// * `name` - The path of the entry in the archive.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<CopyTask>` - The task which wasn't selected.
+    pub fn skipped_task_of(config: &Config, name: &str) -> Option<CopyTask> {
+        let belongs_to = |task: &CopyTask| {
+            [false, true].into_iter().any(|is_dir| {
+                PathMapping::new(task, is_dir).local_path_of(name).is_some()
+            })
+        };
+
+        if config.copy_tasks.iter().any(belongs_to) {
+            return None;
+        }
+        config.skipped_tasks.iter().find(|task| belongs_to(task)).cloned()
+    }
+
+    /// Deletes the snapshots which are not kept by the retention policy
+    /// (only if the `zip_path` is a snapshot template). The snapshot which
+    /// was just created is always kept.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the old snapshots were deleted.
+    /// * `Err(Error)` - If a snapshot cannot be deleted.
+    fn prune_snapshots(&self) -> Result<(), Error> {
+        let (Some(template), Some(retention)) =
+            (&self.config.snapshot_template, &self.config.retention) else {
+            return Ok(());
+        };
+
+        let snapshots = Snapshot::list(template)?;
+        let created: Vec<_> = snapshots.iter()
+            .map(|snapshot| snapshot.created)
+            .collect();
+        let current = Path::new(&self.config.zip_path).file_name();
+
+        let keep = retention.select(&created);
+        for (snapshot, keep) in snapshots.iter().zip(keep) {
+            if keep || snapshot.path.file_name() == current {
+                continue;
+            }
+
+            println!("Removing old snapshot: {}", snapshot.path.display());
+            if snapshot.path.is_dir() {
+                // A mirror might contain read-only directories
+                FileMetadata::make_tree_writable(&snapshot.path)?;
+                fs::remove_dir_all(&snapshot.path)?;
+            } else {
+                fs::remove_file(&snapshot.path)?;
+            }
+        }
 
         Ok(())
     }
@@ -90,105 +570,341 @@ impl ZipCreatorController {
         );
 
         // Print error message
-        let error_message = match error_type {
-            TaskError::PathNotFound => { "Path not found" },
-            TaskError::MetadataError => { "Error reading metadata" },
-            TaskError::PathNotFileOrFolder => { "Path not of file or folder" }
-            TaskError::FileCopyError => { "Error copying file" }
-            TaskError::FolderCopyError  => { "Error copying folder" }
-        };
-
         println!(
             "{}",
-            format!("!!! {}: {}", error_message, task.source).red().bold()
+            format!("!!! {}: {}", error_type.message(), task.source)
+                .red().bold()
         );
     }
 
-    /// Adds a file or folder to the ZIP archive.
-    /// If the source is a file, it will be added to the ZIP archive.
-    /// If the source is a folder, all files and subfolders will be added.
+    /// Collects all entries of a copy task.
+    /// If the source is a file, it results in a single entry.
+    /// If the source is a folder, all files and subfolders are collected.
+    /// Symbolic links are followed, stored or skipped depending on the
+    /// symlink policy of the task.
     ///
     /// # Arguments
     ///
-    /// * `zip` - Mutable reference to the ZipWriter.
     /// * `task` - The copy task that contains the source and target paths.
-    /// * `metadata` - The metadata of the source path.
-    fn add_item_to_zip<W: Write + Seek>(
-        &mut self, zip: &mut ZipWriter<W>, task: CopyTask, metadata: Metadata
-    ) {
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<ArchiveEntry>)` - The entries of the task.
+    /// * `Err(TaskError)` - If the source cannot be read.
+    pub fn collect_entries(task: &CopyTask)
+    -> Result<Vec<ArchiveEntry>, TaskError> {
         let source_path = Path::new(&task.source);
 
+        // Check if source exists (without following symbolic links)
+        let mut metadata = fs::symlink_metadata(source_path)
+            .map_err(|_e| TaskError::PathNotFound)?;
+
+        if metadata.file_type().is_symlink() {
+            match task.symlinks.as_str() {
+                "skip" => {
+                    println!("Skipping symlink: {}", task.source);
+                    return Ok(Vec::new());
+                },
+                "store" => {
+                    let target = fs::read_link(source_path)
+                        .map_err(|_e| TaskError::MetadataError)?;
+                    return Ok(vec![ArchiveEntry {
+                        zip_path: PathMapping::new(task, false).archive_path,
+                        source: source_path.to_path_buf(),
+                        kind: EntryKind::Symlink(target)
+                    }]);
+                },
+                _ => {
+                    metadata = fs::metadata(source_path)
+                        .map_err(|_e| TaskError::PathNotFound)?;
+                }
+            }
+        }
+
         // Check if source is a file or directory
         if metadata.is_file() {
-                // Get filename and target path
-                let filename = source_path.file_name().unwrap()
-                                                 .to_string_lossy().to_string();
+            Ok(vec![ArchiveEntry {
+                zip_path: PathMapping::new(task, false).archive_path,
+                source: source_path.to_path_buf(),
+                kind: EntryKind::File
+            }])
+        } else if metadata.is_dir() {
+            let zip_base = PathMapping::new(task, true).archive_path;
+            let mut entries = vec![ArchiveEntry {
+                zip_path: zip_base.clone(),
+                source: source_path.to_path_buf(),
+                kind: EntryKind::Directory
+            }];
+            let mut ancestors = HashSet::new();
+            Self::collect_directory_entries(
+                source_path, &zip_base, task, &mut ancestors, &mut entries
+            ).map_err(|_e| TaskError::FolderCopyError)?;
+            Ok(entries)
+        } else {
+            // Path is neither file nor directory; i. e. socket etc.
+            Err(TaskError::PathNotFileOrFolder)
+        }
+    }
 
-                let target_path = Self::get_target_file_path(
-                    filename.clone(), task.clone()
-                );
+    /// Recursively collects all files and subdirectories within a directory.
+    /// Entries are sorted by name so the archive has a deterministic order.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir_path` - The path of the directory being processed.
+    /// * `zip_base` - The base path within the ZIP archive.
+    /// * `task` - The copy task that contains the symlink policy.
+    /// * `ancestors` - The canonical paths of all directories above this one
+    ///   (used to detect symlink loops).
+    /// * `entries` - The collected entries.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the directory was successfully read.
+    /// * `Err(Error)` - If an error occurs.
+    fn collect_directory_entries(
+        dir_path: &Path,
+        zip_base: &str,
+        task: &CopyTask,
+        ancestors: &mut HashSet<PathBuf>,
+        entries: &mut Vec<ArchiveEntry>
+    ) -> Result<(), Error> {
+        let canonical_path = fs::canonicalize(dir_path)?;
+        ancestors.insert(canonical_path.clone());
 
-                // Add file to ZIP
-                println!("Adding file: {} -> {}", task.source, target_path);
-                if let Err(_e) = Self::add_file_to_zip(
-                    zip, &task.source, &target_path
-                ) {
-                    self.store_missing(task.clone(), TaskError::FileCopyError);
-                }
-            } else if metadata.is_dir() {
-                if task.target.is_empty() {
-                    // If no target is specified, add the whole folder
-                    println!("Adding folder: {}", task.source);
-                    if let Err(_e) = Self::add_directory(zip, &task.source, "")
-                    {
-                        self.store_missing(
-                            task.clone(), TaskError::FolderCopyError
-                        );
+        let mut dir_entries = fs::read_dir(dir_path)?
+            .collect::<Result<Vec<_>, Error>>()?;
+        dir_entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in dir_entries {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let zip_path = if zip_base.is_empty() {
+                name
+            } else {
+                format!("{}/{}", zip_base, name)
+            };
+
+            let mut file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                match task.symlinks.as_str() {
+                    "skip" => continue,
+                    "store" => {
+                        entries.push(ArchiveEntry {
+                            zip_path,
+                            source: path.clone(),
+                            kind: EntryKind::Symlink(fs::read_link(&path)?)
+                        });
+                        continue;
+                    },
+                    _ => match fs::metadata(&path) {
+                        Ok(metadata) => file_type = metadata.file_type(),
+                        Err(_e) => {
+                            println!(
+                                "{}",
+                                format!("!!! Skipping broken symlink: {}",
+                                        path.display()).yellow().bold()
+                            );
+                            continue;
+                        }
                     }
-                } else {
-                    // Add folder with target path
-                    println!("Adding folder with target path: {} -> {}",
-                             task.source, task.target);
-                    if let Err(_e) = Self::add_directory(
-                        zip, &task.source, &task.target
-                    ) {
-                        self.store_missing(
-                            task.clone(), TaskError::FolderCopyError
-                        );
+                }
+            }
+
+            // Check if entry is a directory or file
+            if file_type.is_dir() {
+                // A directory that is its own ancestor can only be reached
+                // via a symbolic link loop
+                if ancestors.contains(&fs::canonicalize(&path)?) {
+                    println!(
+                        "{}",
+                        format!("!!! Skipping symlink loop: {}", path.display())
+                            .yellow().bold()
+                    );
+                    continue;
+                }
+
+                // Recursively go into the directory
+                entries.push(ArchiveEntry {
+                    zip_path: zip_path.clone(),
+                    source: path.clone(),
+                    kind: EntryKind::Directory
+                });
+                Self::collect_directory_entries(
+                    &path, &zip_path, task, ancestors, entries
+                )?;
+            } else if file_type.is_file() {
+                entries.push(ArchiveEntry {
+                    zip_path,
+                    source: path,
+                    kind: EntryKind::File
+                });
+            }
+        }
+
+        ancestors.remove(&canonical_path);
+        Ok(())
+    }
+
+    /// Adds the collected entries of a copy task to the archive.
+    /// The files are compressed in parallel in batches (bounded by the
+    /// number of files and their total size) and appended in their original
+    /// order, so the archive is deterministic. Large files are streamed into
+    /// the archive instead.
+    /// If an entry cannot be added, the task is marked as missing.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `pool` - The worker pool which compresses the files.
+    /// * `task` - The copy task the entries belong to.
+    /// * `entries` - The entries to add.
+    fn add_entries_to_zip(
+        &mut self, writer: &mut dyn ArchiveWriter, pool: &ThreadPool,
+        task: &CopyTask, entries: Vec<ArchiveEntry>
+    ) {
+        let Some(compression) = Compression::from_settings(&self.config, task)
+        else {
+            self.store_missing(task.clone(), TaskError::InvalidCompression);
+            return;
+        };
+        let encoder = writer.file_encoder(compression);
+
+        // The sizes of the files which are compressed in memory (large files
+        // are streamed while they are appended)
+        let sizes: Vec<Option<u64>> = entries.iter().map(|entry| {
+            (entry.kind == EntryKind::File)
+                .then(|| fs::metadata(&entry.source).ok())
+                .flatten()
+                .map(|metadata| metadata.len())
+                .filter(|size| *size <= STREAM_THRESHOLD)
+        }).collect();
+
+        let mut start = 0;
+        while start < entries.len() {
+            let mut end = start;
+            let mut batch_bytes = 0;
+            while end < entries.len() && end - start < PARALLEL_BATCH_SIZE {
+                batch_bytes += sizes[end].unwrap_or(0);
+                if end > start && batch_bytes > MAX_BATCH_BYTES {
+                    break;
+                }
+                end += 1;
+            }
+            let batch = &entries[start..end];
+            let batch_sizes = &sizes[start..end];
+            start = end;
+
+            // Files which didn't change since the previous update are copied
+            // from its archive instead of being compressed again
+            let unchanged: Vec<_> = batch.iter()
+                .map(|entry| self.unchanged_file(entry))
+                .collect();
+
+            // Compress the other files of the batch on the worker pool
+            let manifest = self.manifest.as_ref();
+            let compressed_files: Vec<_> = pool.install(|| {
+                batch.par_iter().zip(batch_sizes).zip(&unchanged)
+                    .map(|((entry, size), unchanged)| {
+                        (size.is_some() && unchanged.is_none()).then(|| {
+                            Self::compress_file(entry, &encoder, manifest)
+                        })
+                    }).collect()
+            });
+
+            // Append the entries in their original order
+            let files = compressed_files.into_iter().zip(unchanged);
+            for (entry, (compressed, unchanged)) in batch.iter().zip(files) {
+                let (result, error_type) = match (&entry.kind, compressed) {
+                    (EntryKind::File, compressed) => {
+                        println!("Adding file: {} -> {}",
+                                 entry.source.display(), entry.zip_path);
+                        (self.add_file_or_copy(
+                            writer, entry, compressed, unchanged, compression,
+                            task
+                        ), TaskError::FileCopyError)
+                    },
+                    (EntryKind::Symlink(target), _) => {
+                        println!("Adding symlink: {} -> {}",
+                                 entry.zip_path, target.display());
+                        (self.add_symlink_to_zip(writer, entry, target),
+                         TaskError::FileCopyError)
+                    },
+                    _ => {
+                        println!("Adding directory: '{}'", entry.zip_path);
+                        (self.add_directory(writer, entry),
+                         TaskError::FolderCopyError)
                     }
+                };
+
+                if let Err(_e) = result {
+                    self.store_missing(task.clone(), error_type);
+                    return;
                 }
-            } else {
-                // Path is neither file nor directory; i. e. Symlink etc.
-                self.store_missing(task.clone(), TaskError::PathNotFileOrFolder);
             }
+        }
     }
 
-    /// Returns the target file path for a file based on the target path in the
-    /// copy task.
+    /// Returns the file of an entry in the archive of the previous update if
+    /// the file wasn't changed since then.
     ///
     /// # Arguments
     ///
-    /// * `filename` - The name of the file.
-    /// * `task` - The copy task that contains the target path.
+    /// * `entry` - The entry with the path of the file on disk and the path
+    ///   within the archive.
     ///
     /// # Returns
     ///
-    /// * `String` - The target file path.
-    fn get_target_file_path(filename: String, task: CopyTask) -> String {
-        if task.target.is_empty() {
-            // Use file name if target path is empty
-            filename
-        } else if task.target.ends_with('/') {
-            // If target path ends with a slash, append the file name
-            format!("{}{}", task.target, filename)
-        } else if Path::new(&task.target).extension().is_none() {
-            // If the target doesn't have a file extension, treat it as a
-            // directory path
-            format!("{}/{}", task.target, filename)
-        } else {
-            // Use target as full path
-            task.target.clone()
+    /// * `Option<WrittenFile>` - The file of the previous update (`None` if
+    ///   the file wasn't part of it or changed).
+    fn unchanged_file(&self, entry: &ArchiveEntry) -> Option<WrittenFile> {
+        if entry.kind != EntryKind::File {
+            return None;
         }
+        let written = self.written.as_ref()?.get(&entry.zip_path)?;
+        let metadata = fs::metadata(&entry.source).ok()?;
+
+        (written.source == entry.source
+            && written.modified == metadata.modified().ok()?
+            && written.size == metadata.len())
+            .then(|| written.clone())
+    }
+
+    /// Reads a single file and prepares it for the archive writer (e.g.
+    /// compresses it). This function runs on the worker pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry with the path of the file on disk and the path
+    ///   within the archive.
+    /// * `encoder` - The encoder of the archive writer.
+    /// * `manifest` - The manifest (if enabled), used to hash the content.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CompressedFile)` - The compressed file.
+    /// * `Err(Error)` - If an error occurs.
+    fn compress_file(
+        entry: &ArchiveEntry,
+        encoder: &FileEncoder,
+        manifest: Option<&Manifest>
+    ) -> Result<CompressedFile, Error> {
+        // Read file
+        let mut file = File::open(&entry.source)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        // Compress file with its modification time and permissions
+        let metadata = FileMetadata::from_path(&entry.source)?;
+        let size = buffer.len() as u64;
+        let hash = manifest.map(|manifest| manifest.hash(&buffer));
+
+        Ok(CompressedFile {
+            data: encoder(&entry.zip_path, buffer, metadata)?,
+            size,
+            hash,
+            metadata
+        })
     }
 
     /// Displays a message that the ZIP files was created and checks if all
@@ -231,157 +947,334 @@ impl ZipCreatorController {
         }
     }
 
-    /// Adds a single file to the ZIP archive.
+    /// Adds a single file to the archive. A file which didn't change since
+    /// the previous update is copied from its archive, all other files which
+    /// weren't compressed on the worker pool are streamed into the archive.
     ///
     /// # Arguments
     ///
-    /// * `zip` - Mutable reference to the ZipWriter.
-    /// * `file_path` - Path to the file to be added.
-    /// * `zip_path` - Path within the ZIP archive where the file should be
-    ///                stored.
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `entry` - The entry with the path of the file on disk and the path
+    ///   within the archive.
+    /// * `compressed` - The file compressed on the worker pool (if any).
+    /// * `unchanged` - The file of the previous update (if unchanged).
+    /// * `compression` - The compression settings of the task.
+    /// * `task` - The copy task the file belongs to.
     ///
     /// # Returns
     ///
     /// * `Ok(())` - If the file was successfully added.
-    /// * `Err(zip::result::ZipError)` - If an error occurs.
-    fn add_file_to_zip<T: Write + Seek>(
-        zip: &mut ZipWriter<T>,
-        file_path: &str,
-        zip_path: &str,
-    ) -> zip::result::ZipResult<()> {
-        // Create directories in ZIP if necessary (this avoids errors)
-        if let Some(parent) = Path::new(zip_path).parent() {
-            let dir = parent.to_string_lossy().to_string();
-            if !dir.is_empty() {
-                zip.add_directory::<_, ()>(
-                    format!("{}/", dir), FileOptions::default()
-                )?;
+    /// * `Err(Error)` - If an error occurs.
+    fn add_file_or_copy(
+        &mut self,
+        writer: &mut dyn ArchiveWriter,
+        entry: &ArchiveEntry,
+        compressed: Option<Result<CompressedFile, Error>>,
+        unchanged: Option<WrittenFile>,
+        compression: Compression,
+        task: &CopyTask
+    ) -> Result<(), Error> {
+        if let Some(file) = unchanged {
+            self.add_parent_directories(writer, &entry.zip_path)?;
+            if writer.copy_unchanged(&entry.zip_path)? {
+                self.add_manifest_entry(
+                    &entry.source.to_string_lossy(), &entry.zip_path,
+                    file.size, file.hash.clone(), file.metadata, task
+                );
+                self.writing.insert(entry.zip_path.clone(), file);
+                return Ok(());
             }
         }
 
-        // Read file
-        let mut file = File::open(file_path)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
+        match compressed {
+            Some(compressed) => {
+                self.add_file_to_zip(writer, entry, compressed?, task)
+            },
+            None => self.stream_file_to_zip(writer, entry, compression, task)
+        }
+    }
+
+    /// Streams a large file into the archive while it is read (and hashed),
+    /// without keeping it in memory. Missing parent directories are added
+    /// as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `entry` - The entry with the path of the file on disk and the path
+    ///   within the archive.
+    /// * `compression` - The compression settings of the task.
+    /// * `task` - The copy task the file belongs to.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the file was successfully added.
+    /// * `Err(Error)` - If an error occurs or the file changed while it was
+    ///   read.
+    fn stream_file_to_zip(
+        &mut self,
+        writer: &mut dyn ArchiveWriter,
+        entry: &ArchiveEntry,
+        compression: Compression,
+        task: &CopyTask
+    ) -> Result<(), Error> {
+        self.add_parent_directories(writer, &entry.zip_path)?;
 
-        // Add file to ZIP
-        zip.start_file::<_, ()>(
-            zip_path,
-            FileOptions::default().compression_method(
-                CompressionMethod::Deflated
-            )
+        let metadata = FileMetadata::from_path(&entry.source)?;
+        let file = File::open(&entry.source)?;
+        let size = file.metadata()?.len();
+        let mut reader = HashingReader {
+            inner: file,
+            hasher: self.manifest.as_ref().map(Manifest::hasher),
+            size: 0
+        };
+        writer.write_stream(
+            &entry.zip_path, &mut reader, size, compression, metadata
         )?;
-        zip.write_all(&buffer)?;
+        if reader.size != size {
+            return Err(Error::other(format!(
+                "File changed while it was read: {}", entry.source.display()
+            )));
+        }
+
+        let hash = reader.hasher.map(ContentHasher::finish);
+        self.remember_written_file(entry, size, hash.clone(), metadata)?;
+        self.add_manifest_entry(
+            &entry.source.to_string_lossy(), &entry.zip_path, size, hash,
+            metadata, task
+        );
 
         Ok(())
     }
 
-    /// Adds a directory to the ZIP archive.
+    /// Adds a single compressed file to the archive. Missing parent
+    /// directories are added as well.
     ///
     /// # Arguments
     ///
-    /// * `zip` - Mutable reference to the ZipWriter.
-    /// * `dir_path` - The path to the directory to add.
-    /// * `target_prefix` - The target prefix in the ZIP archive.
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `entry` - The entry with the path of the file on disk and the path
+    ///   within the archive.
+    /// * `file` - The compressed file.
+    /// * `task` - The copy task the file belongs to.
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - If the directory was successfully added.
-    /// * `Err(zip::result::ZipError)` - If an error occurs.
-    fn add_directory<T: Write + Seek>(
-        zip: &mut ZipWriter<T>,
-        dir_path: &str,
-        target_prefix: &str
-    ) -> zip::result::ZipResult<()> {
-        // Extract the directory name from the path
-        let dir_name = Path::new(dir_path)
-            .file_name()
-            .map_or(
-                dir_path.to_string(), |name| name.to_string_lossy().into_owned()
-            );
-
-        // Determine the target directory name in the ZIP
-        let target_dir_name = if target_prefix.is_empty() {
-            dir_name
-        } else {
-            target_prefix.to_string()
-        };
+    /// * `Ok(())` - If the file was successfully added.
+    /// * `Err(Error)` - If an error occurs.
+    fn add_file_to_zip(
+        &mut self,
+        writer: &mut dyn ArchiveWriter,
+        entry: &ArchiveEntry,
+        file: CompressedFile,
+        task: &CopyTask
+    ) -> Result<(), Error> {
+        // Create directories in ZIP if necessary (this avoids errors)
+        self.add_parent_directories(writer, &entry.zip_path)?;
 
-        // Add main directory to ZIP with trailing "/"
-        println!("Adding directory: '{}'", target_dir_name);
-        zip.add_directory::<_, ()>(
-            format!("{}/", target_dir_name), FileOptions::default()
+        self.remember_written_file(
+            entry, file.size, file.hash.clone(), file.metadata
         )?;
+        writer.write_file(&entry.zip_path, file.data, file.metadata)?;
+        self.add_manifest_entry(
+            &entry.source.to_string_lossy(), &entry.zip_path, file.size,
+            file.hash, file.metadata, task
+        );
 
-        // Add all subdirectories and files recursively
-        Self::add_directory_recursively(zip, dir_path, &target_dir_name)?;
+        Ok(())
+    }
 
+    /// Remembers a file which was written to the archive, so the next update
+    /// can copy it if it doesn't change (only in watch mode).
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry with the path of the file on disk and the path
+    ///   within the archive.
+    /// * `size` - The uncompressed size of the file.
+    /// * `hash` - The hash of the file content (if the manifest is enabled).
+    /// * `metadata` - The modification time and permissions of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the file was remembered (or watch mode is disabled).
+    /// * `Err(Error)` - If the file cannot be accessed.
+    fn remember_written_file(
+        &mut self, entry: &ArchiveEntry, size: u64, hash: Option<String>,
+        metadata: FileMetadata
+    ) -> Result<(), Error> {
+        if self.written.is_some()
+        && let Ok(modified) = fs::metadata(&entry.source)?.modified() {
+            self.writing.insert(entry.zip_path.clone(), WrittenFile {
+                source: entry.source.clone(),
+                modified,
+                size,
+                hash,
+                metadata
+            });
+        }
         Ok(())
     }
 
-    /// Recursively adds all files and subdirectories within a directory to the
-    /// ZIP archive.
+    /// Adds a directory entry with its modification time and permissions to
+    /// the archive. Directories which were already added are skipped.
     ///
     /// # Arguments
     ///
-    /// * `zip` - Mutable reference to the ZipWriter.
-    /// * `base_path` - The base path of the directory being processed.
-    /// * `zip_base` - The base path within the ZIP archive.
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `entry` - The entry with the path of the directory.
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - If all files and subdirectories were successfully added.
-    /// * `Err(zip::result::ZipError)` - If an error occurs.
-    fn add_directory_recursively<T: Write + Seek>(
-        zip: &mut ZipWriter<T>,
-        base_path: &str,
-        zip_base: &str,
-    ) -> zip::result::ZipResult<()> {
-        for entry in fs::read_dir(base_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            let name = path.file_name().unwrap().to_string_lossy();
-            let zip_path = if zip_base.ends_with('/') {
-                format!("{}{}", zip_base, name)
-            } else {
-                format!("{}/{}", zip_base, name)
-            };
+    /// * `Ok(())` - If the directory was successfully added.
+    /// * `Err(Error)` - If an error occurs.
+    fn add_directory(
+        &mut self,
+        writer: &mut dyn ArchiveWriter,
+        entry: &ArchiveEntry
+    ) -> Result<(), Error> {
+        self.add_parent_directories(writer, &entry.zip_path)?;
 
-            // Check if entry is a directory or file
-            if path.is_dir() {
-                // Recursively go into the directory
-                zip.add_directory::<_, ()>(
-                    format!("{}/", zip_path), FileOptions::default()
-                )?;
-                Self::add_directory_recursively(
-                    zip, path.to_str().unwrap(), &zip_path
-                )?;
-            } else if path.is_file() {
-                // Add file to ZIP
-                let mut file = File::open(&path)?;
-                let mut buffer = Vec::new();
-                file.read_to_end(&mut buffer)?;
-
-                zip.start_file::<_, ()>(
-                    &zip_path,
-                    FileOptions::default().compression_method(
-                        CompressionMethod::Deflated
-                    )
-                )?;
-                zip.write_all(&buffer)?;
+        let dir_name = format!("{}/", entry.zip_path);
+        if self.added_directories.insert(dir_name) {
+            let metadata = FileMetadata::from_path(&entry.source)?;
+            writer.write_directory(&entry.zip_path, metadata)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds all parent directories of a path to the archive which were not
+    /// added yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `zip_path` - The path within the archive.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the directories were successfully added.
+    /// * `Err(Error)` - If an error occurs.
+    fn add_parent_directories(
+        &mut self,
+        writer: &mut dyn ArchiveWriter,
+        zip_path: &str
+    ) -> Result<(), Error> {
+        let mut parents: Vec<String> = Path::new(zip_path).ancestors()
+            .skip(1)
+            .map(|parent| parent.to_string_lossy().to_string())
+            .filter(|parent| !parent.is_empty())
+            .collect();
+        parents.reverse();
+
+        for parent in parents {
+            if self.added_directories.insert(format!("{}/", parent)) {
+                writer.write_directory(&parent, FileMetadata::default())?;
             }
         }
 
         Ok(())
     }
 
+    /// Adds a symbolic link to the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Mutable reference to the archive writer.
+    /// * `entry` - The entry with the path of the link.
+    /// * `target` - The target path of the link.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the link was successfully added.
+    /// * `Err(Error)` - If an error occurs.
+    fn add_symlink_to_zip(
+        &mut self,
+        writer: &mut dyn ArchiveWriter,
+        entry: &ArchiveEntry,
+        target: &Path
+    ) -> Result<(), Error> {
+        self.add_parent_directories(writer, &entry.zip_path)?;
+        writer.write_symlink(&entry.zip_path, target)
+    }
+
+    /// Adds an entry for a file to the manifest (if the manifest is enabled).
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - Path to the file on disk.
+    /// * `zip_path` - Path of the file within the ZIP archive.
+    /// * `size` - The size of the file in bytes.
+    /// * `hash` - The hash of the file content.
+    /// * `metadata` - The modification time and permissions of the file.
+    /// * `task` - The copy task the file belongs to.
+    fn add_manifest_entry(
+        &mut self,
+        file_path: &str,
+        zip_path: &str,
+        size: u64,
+        hash: Option<String>,
+        metadata: FileMetadata,
+        task: &CopyTask
+    ) {
+        let (Some(manifest), Some(hash)) = (self.manifest.as_mut(), hash)
+        else {
+            return;
+        };
+
+        let entry = ManifestEntry {
+            path: zip_path.to_string(),
+            source: file_path.to_string(),
+            task: task.description.clone(),
+            size,
+            mtime: metadata.mtime,
+            permissions: metadata.permissions,
+            hash
+        };
+        manifest.entries.push(entry);
+    }
+
+    /// Writes the manifest to the archive (if the manifest is enabled).
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Mutable reference to the archive writer.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the manifest was written or is disabled.
+    /// * `Err(Error)` - If an error occurs.
+    fn write_manifest(&mut self, writer: &mut dyn ArchiveWriter)
+    -> Result<(), Error> {
+        let Some(manifest) = &self.manifest else {
+            return Ok(());
+        };
+
+        println!("Adding manifest: {}", MANIFEST_PATH);
+        let content = serde_json::to_vec_pretty(manifest)?;
+        let encoder = writer.file_encoder(Compression {
+            method: CompressionMethod::Deflated,
+            level: None
+        });
+        let metadata = FileMetadata {
+            mtime: Some(chrono::Local::now().timestamp()),
+            permissions: Some(0o644),
+            ..FileMetadata::default()
+        };
+
+        self.add_parent_directories(writer, MANIFEST_PATH)?;
+        let data = encoder(MANIFEST_PATH, content, metadata)?;
+        writer.write_file(MANIFEST_PATH, data, metadata)
+    }
+
     /// Checks if all files were correctly stored in the ZIP archive.
     ///
     /// # Arguments
     ///
     /// * `config` - Reference to the configuration.
     /// * `missing_items` - HashMap of items that were missing during
-    ///                     ZIP creation.
+    ///   ZIP creation.
     ///
     /// # Returns
     ///
@@ -390,7 +1283,7 @@ impl ZipCreatorController {
     /// * `Err(Error)` - If an error occurs during the check.
     fn check_zip(&mut self) -> Result<bool, Error> {
         // Collect all file paths from the ZIP
-        let zip_files = Self::get_file_list_from_zip(&self.config.zip_path)?;
+        let zip_files = Self::get_file_list_from_zip(&self.config)?;
 
         // Check all copy tasks
         for task in &self.config.copy_tasks {
@@ -402,16 +1295,28 @@ impl ZipCreatorController {
             println!("Checking task: Source={}, Target={}",
                      task.source, task.target);
 
+            // Check symbolic links which are not followed
+            if path.is_symlink() && task.symlinks == "skip" {
+                continue;
+            } else if path.is_symlink() && task.symlinks == "store" {
+                let entries = Self::collect_entries(task).unwrap_or_default();
+                if !entries.iter().all(|e| zip_files.contains(&e.zip_path)) {
+                    println!("{}", format!(
+                        "!!! Symlink not found in ZIP: '{}'", task.source
+                    ).red().bold());
+                    return Ok(false);
+                }
+                continue;
+            }
+
             // Check file or directory
             if path.is_file() {
-                if Self::check_if_file_in_zip(
-                    zip_files.clone(), task.clone(), &path
-                ) {
+                if Self::check_if_file_in_zip(zip_files.clone(), task.clone()) {
                     return Ok(false);
                 }
             } else if path.is_dir() {
                 if Self::check_if_directory_in_zip(
-                    zip_files.clone(), task.clone(), &path
+                    zip_files.clone(), task.clone()
                 ) {
                     return Ok(false);
                 }
@@ -442,33 +1347,32 @@ impl ZipCreatorController {
         Ok(true)
     }
 
-    /// Returns a list of all files in the ZIP archive.
+    /// Returns a list of all files in the archive. Directories are listed
+    /// with a trailing slash.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config with the path and the format of the archive.
     ///
     /// # Returns
     ///
     /// * `Ok(HashSet<String>)` - A HashSet with all file paths in the ZIP.
     /// * `Err(Error)` - If an error occurs during the operation.
-    pub fn get_file_list_from_zip(zip_path: &str)
+    pub fn get_file_list_from_zip(config: &Config)
     -> Result<HashSet<String>, Error> {
-        // Open the ZIP file that was just created
-        let file = File::open(zip_path)?;
-        let reader = BufReader::new(file);
-        let mut archive = ZipArchive::new(reader)?;
+        // Open the archive that was just created
+        let mut reader = backend::open_reader(config)?;
 
         // Collect all file paths from the ZIP
         let mut zip_files = HashSet::new();
 
         println!("Files in the ZIP archive:");
-        for i in 0..archive.len() {
-            let file = archive.by_index(i)?;
-            let name = file.name().to_string();
-
-            // // Remove trailing slash
-            // let name = if name.ends_with('/') {
-            //     name[..name.len() - 1].to_string()
-            // } else {
-            //     name
-            // };
+        for entry in reader.list_entries()? {
+            let name = if entry.kind == EntryKind::Directory {
+                format!("{}/", entry.name)
+            } else {
+                entry.name
+            };
 
             println!("  - '{}'", name);
             zip_files.insert(name);
@@ -490,7 +1394,7 @@ impl ZipCreatorController {
     /// # Returns
     ///
     /// * `HashSet<String>` - A HashSet with all file paths in the ZIP archive
-    ///                       and the reconstructed directories.
+    ///   and the reconstructed directories.
     pub fn add_reconstructed_dirs_to_file_list(mut zip_files: HashSet<String>)
     -> HashSet<String> {
         let mut reconstructed_dirs = HashSet::new();
@@ -506,7 +1410,7 @@ impl ZipCreatorController {
                     break;
                 }
 
-                println!("  - '{}'", parent.to_string_lossy().to_string());
+                println!("  - '{}'", parent.to_string_lossy());
                 reconstructed_dirs.insert(parent.to_string_lossy().to_string());
                 current = parent;
             }
@@ -535,9 +1439,9 @@ impl ZipCreatorController {
                     task_source, task_target
                 ).yellow().bold()
             );
-            return true;
+            true
         } else {
-            return false;
+            false
         }
     }
 
@@ -547,23 +1451,14 @@ impl ZipCreatorController {
     ///
     /// * `zip_files` - A HashSet with all file paths in the ZIP archive.
     /// * `task` - The copy task that contains the source and target paths.
-    /// * `path` - The path of the file to check.
     ///
     /// # Returns
     ///
     /// * `bool` - True if the file is not in the ZIP archive, false otherwise.
-    fn check_if_file_in_zip(
-        zip_files: HashSet<String>,
-        task: CopyTask,
-        path: &Path
-    ) -> bool {
-        // Get the filename
-        let filename = path.file_name().unwrap().to_string_lossy().to_string();
-
+    fn check_if_file_in_zip(zip_files: HashSet<String>, task: CopyTask)
+    -> bool {
         // Check if the file is in the ZIP
-        let expected_path = Self::get_target_file_path(
-            filename.clone(), task.clone()
-        );
+        let expected_path = PathMapping::new(&task, false).archive_path;
         println!("Loooking for file: '{}'", expected_path);
 
         // Check if the file is in the ZIP archive
@@ -571,9 +1466,9 @@ impl ZipCreatorController {
             println!("{}", format!("!!! Datei nicht gefunden in ZIP: '{}' \
                                    (Quelle: '{}')",
                                    expected_path, task.source).red().bold());
-            return true;
+            true
         } else {
-            return false;
+            false
         }
     }
 
@@ -583,34 +1478,17 @@ impl ZipCreatorController {
     ///
     /// * `zip_files` - A HashSet with all file paths in the ZIP archive.
     /// * `task` - The copy task that contains the source and target paths.
-    /// * `path` - The path of the directory to check.
     ///
     /// # Returns
     ///
     /// * `bool` - True if the directory is not in the ZIP archive, false
-    ///            otherwise.
-    fn check_if_directory_in_zip(
-        zip_files: HashSet<String>,
-        task: CopyTask,
-        path: &Path
-    ) -> bool {
+    ///   otherwise.
+    fn check_if_directory_in_zip(zip_files: HashSet<String>, task: CopyTask)
+    -> bool {
         // Determine the expected directory path in the ZIP
-        let expected_dir = if task.target.is_empty() {
-            // If target is empty, use the directory name
-            let dir_name = path.file_name()
-                .map_or(
-                    path.to_string_lossy().to_string(),
-                    |name| name.to_string_lossy().to_string()
-                );
-            format!("{}/", dir_name)
-        } else {
-            // If target is not empty, use the target path
-            if task.target.ends_with('/') {
-                task.target.clone()
-            } else {
-                format!("{}/", task.target)
-            }
-        };
+        let expected_dir = format!(
+            "{}/", PathMapping::new(&task, true).archive_path
+        );
 
         // Check if the directory is in the ZIP archive
         println!("Looking for directory: '{}'", expected_dir);
@@ -630,23 +1508,447 @@ impl ZipCreatorController {
             for p in zip_files.iter().filter(|p| p.ends_with("/")) {
                 println!("  - '{}'", p);
             }
-            return true;
+            true
         } else {
-            return false;
+            false
+        }
+    }
+}
+
+
+/// A file which was prepared by the encoder of the archive writer.
+///
+/// # Fields
+///
+/// * `data` - The prepared content (e.g. an in-memory ZIP archive containing
+///   only the compressed file).
+/// * `size` - The uncompressed size of the file.
+/// * `hash` - The hash of the file content (if the manifest is enabled).
+/// * `metadata` - The modification time and permissions of the file.
+struct CompressedFile {
+    data: Vec<u8>,
+    size: u64,
+    hash: Option<String>,
+    metadata: FileMetadata
+}
+
+/// Reads a file which is streamed into the archive and hashes its content
+/// on the way.
+///
+/// # Fields
+///
+/// * `inner` - The file.
+/// * `hasher` - The hasher (if the manifest is enabled).
+/// * `size` - The number of bytes read so far.
+struct HashingReader {
+    inner: File,
+    hasher: Option<ContentHasher>,
+    size: u64
+}
+
+impl Read for HashingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(hasher) = self.hasher.as_mut() {
+            hasher.update(&buf[..read]);
         }
+        self.size += read as u64;
+        Ok(read)
     }
 }
 
+/// A file which was written to the archive, kept for the next incremental
+/// update.
+///
+/// # Fields
+///
+/// * `source` - The path of the file on disk.
+/// * `modified` - The modification time of the source file.
+/// * `size` - The uncompressed size of the file.
+/// * `hash` - The hash of the file content (if the manifest is enabled).
+/// * `metadata` - The modification time and permissions of the file.
+#[derive(Clone)]
+struct WrittenFile {
+    source: PathBuf,
+    modified: SystemTime,
+    size: u64,
+    hash: Option<String>,
+    metadata: FileMetadata
+}
 
-/// Enumeration for the error types "PathNotFound" and "MetadataError".
+
+/// Enumeration for the error types which can occur while processing a copy
+/// task.
 ///
 /// The "PathNotFound" error is used when a path does not exist.
 /// The "MetadataError" error is used when metadata cannot be retrieved.
 #[derive(Debug)]
-enum TaskError {
+pub enum TaskError {
     PathNotFound,
     MetadataError,
     PathNotFileOrFolder,
     FileCopyError,
     FolderCopyError,
-}
\ No newline at end of file
+    InvalidCompression,
+}
+
+impl TaskError {
+    /// Returns a human-readable message for the error type.
+    pub fn message(&self) -> &'static str {
+        match self {
+            TaskError::PathNotFound => { "Path not found" },
+            TaskError::MetadataError => { "Error reading metadata" },
+            TaskError::PathNotFileOrFolder => { "Path not of file or folder" }
+            TaskError::FileCopyError => { "Error copying file" }
+            TaskError::FolderCopyError  => { "Error copying folder" }
+            TaskError::InvalidCompression => { "Unknown compression method" }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates the config of an archive in a directory with a task for
+    /// every source.
+    fn config(root: &Path, on_error: &str, sources: &[(&str, bool)])
+    -> Config {
+        let tasks: Vec<_> = sources.iter().map(|(source, required)| {
+            serde_json::json!({
+                "source": root.join(source),
+                "description": source,
+                "target": "",
+                "required": if *required { "yes" } else { "no" }
+            })
+        }).collect();
+
+        serde_json::from_value(serde_json::json!({
+            "zip_path": root.join("archive.zip"),
+            "direction": "to_zip",
+            "confirm_new": "no",
+            "confirm_overwrite": "no",
+            "confirm_delete": "no",
+            "on_error": on_error,
+            "copy_tasks": tasks
+        })).unwrap()
+    }
+
+    #[test]
+    fn aborted_run_keeps_the_archive() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let sources = [("a.txt", true), ("b.txt", false)];
+        let mut creator = ZipCreatorController::new(
+            config(root, "abort", &sources)
+        );
+        assert!(creator.start());
+        let archive = fs::read(root.join("archive.zip")).unwrap();
+
+        fs::remove_file(root.join("a.txt")).unwrap();
+        fs::write(root.join("b.txt"), "changed").unwrap();
+        for on_error in ["abort", "abort_without_writing"] {
+            let mut creator = ZipCreatorController::new(
+                config(root, on_error, &sources)
+            );
+            assert!(!creator.start());
+            assert_eq!(fs::read(root.join("archive.zip")).unwrap(), archive);
+            assert!(!root.join("archive.zip.partial").exists());
+        }
+
+        let mut creator = ZipCreatorController::new(
+            config(root, "continue", &sources)
+        );
+        assert!(!creator.start());
+        assert_ne!(fs::read(root.join("archive.zip")).unwrap(), archive);
+    }
+
+    #[test]
+    fn required_setting_is_normalised() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+
+        let with_required = |required: &str| serde_json::from_value::<Config>(
+            serde_json::json!({
+                "zip_path": root.join("archive.zip"),
+                "direction": "to_zip",
+                "confirm_new": "no",
+                "confirm_overwrite": "no",
+                "confirm_delete": "no",
+                "copy_tasks": [
+                    { "source": root.join("a.txt"), "description": "a",
+                      "target": "" },
+                    { "source": root.join("missing.txt"),
+                      "description": "missing", "target": "",
+                      "required": required }
+                ]
+            })
+        );
+
+        let config = with_required("true").unwrap();
+        assert_eq!(config.copy_tasks[1].required, "yes");
+        assert!(!ZipCreatorController::new(config).start());
+        let config = with_required("False").unwrap();
+        assert!(ZipCreatorController::new(config).start());
+        assert!(with_required("on").is_err());
+    }
+
+    #[test]
+    fn update_copies_unchanged_files_from_the_archive() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let sources = [("a.txt", false), ("b.txt", false)];
+        let mut creator = ZipCreatorController::new(
+            config(root, "continue", &sources)
+        );
+        assert!(creator.update(&[]));
+
+        // Same size and modification time, so "a.txt" counts as unchanged
+        let modified = fs::metadata(root.join("a.txt")).unwrap()
+            .modified().unwrap();
+        fs::write(root.join("a.txt"), "x").unwrap();
+        File::options().write(true).open(root.join("a.txt")).unwrap()
+            .set_modified(modified).unwrap();
+        fs::write(root.join("b.txt"), "changed").unwrap();
+        assert!(creator.update(&[root.join("b.txt")]));
+
+        let file = File::open(root.join("archive.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+        assert_eq!(read("a.txt"), "a");
+        assert_eq!(read("b.txt"), "changed");
+    }
+
+    #[test]
+    fn large_files_are_streamed_into_the_archive() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let content: Vec<u8> = (0..STREAM_THRESHOLD + 1000)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        fs::write(root.join("large.bin"), &content).unwrap();
+
+        let mut config = config(root, "continue", &[("large.bin", true)]);
+        config.manifest = "yes".to_string();
+        let mut creator = ZipCreatorController::new(config);
+        assert!(creator.start());
+
+        let file = File::open(root.join("archive.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut read = Vec::new();
+        archive.by_name("large.bin").unwrap().read_to_end(&mut read)
+            .unwrap();
+        assert!(read == content);
+
+        let manifest = creator.manifest.unwrap();
+        assert_eq!(manifest.entries[0].hash, manifest.hash(&content));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mirror_is_updated_inside_of_read_only_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let read_only = fs::Permissions::from_mode(0o555);
+        fs::create_dir(root.join("dir")).unwrap();
+
+        let mut config = config(root, "continue", &[("dir", true)]);
+        config.format = "dir".to_string();
+        config.zip_path = root.join("mirror").to_string_lossy().to_string();
+        for content in ["old", "new"] {
+            fs::set_permissions(root.join("dir"), fs::Permissions::from_mode(
+                0o755
+            )).unwrap();
+            fs::write(root.join("dir/a.txt"), content).unwrap();
+            fs::set_permissions(root.join("dir"), read_only.clone()).unwrap();
+            assert!(ZipCreatorController::new(config.clone()).start());
+        }
+
+        let mirror = root.join("mirror/dir");
+        assert_eq!(fs::read_to_string(mirror.join("a.txt")).unwrap(), "new");
+        let mode = fs::metadata(&mirror).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o555);
+        fs::set_permissions(&mirror, fs::Permissions::from_mode(0o755))
+            .unwrap();
+        fs::set_permissions(root.join("dir"), fs::Permissions::from_mode(
+            0o755
+        )).unwrap();
+    }
+
+    #[test]
+    fn mirror_removes_deleted_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("dir/sub")).unwrap();
+        fs::write(root.join("dir/a.txt"), "a").unwrap();
+        fs::write(root.join("dir/b.txt"), "b").unwrap();
+        fs::write(root.join("dir/sub/c.txt"), "c").unwrap();
+
+        let mut config = config(root, "continue", &[("dir", true)]);
+        config.format = "dir".to_string();
+        config.zip_path = root.join("mirror").to_string_lossy().to_string();
+        assert!(ZipCreatorController::new(config.clone()).start());
+        fs::write(root.join("mirror/other.txt"), "other").unwrap();
+
+        fs::remove_file(root.join("dir/b.txt")).unwrap();
+        fs::remove_dir_all(root.join("dir/sub")).unwrap();
+        assert!(ZipCreatorController::new(config).start());
+
+        let mirror = root.join("mirror");
+        assert!(mirror.join("dir/a.txt").is_file());
+        assert!(!mirror.join("dir/b.txt").exists());
+        assert!(!mirror.join("dir/sub").exists());
+        assert!(mirror.join("other.txt").is_file());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn selective_encrypted_run_reads_the_password_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        // The command counts how often the password is read
+        let mut config = config(
+            root, "continue", &[("a.txt", false), ("b.txt", false)]
+        );
+        config.encryption = "aes256".to_string();
+        config.password_source = Some(PasswordSource::Command(format!(
+            "echo >> '{}'; echo secret", root.join("reads").display()
+        )));
+        assert!(ZipCreatorController::new(config.clone()).start());
+
+        config.select_tasks(&["a.txt".to_string()], &[], &[]);
+        assert!(ZipCreatorController::new(config).start());
+        let reads = fs::read_to_string(root.join("reads")).unwrap();
+        assert_eq!(reads.lines().count(), 2);
+
+        let file = File::open(root.join("archive.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut content = String::new();
+        archive.by_name_decrypt("b.txt", b"secret").unwrap()
+            .read_to_string(&mut content).unwrap();
+        assert_eq!(content, "b");
+    }
+
+    #[test]
+    fn entries_of_unselected_tasks_are_kept_as_they_are() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a".repeat(1000)).unwrap();
+        fs::write(root.join("b.txt"), "b".repeat(1000)).unwrap();
+
+        let mut config = config(
+            root, "continue", &[("a.txt", false), ("b.txt", false)]
+        );
+        assert!(ZipCreatorController::new(config.clone()).start());
+        let mut tar = config.clone();
+
+        // The kept entry isn't recompressed with the new settings
+        config.compression = "stored".to_string();
+        config.select_tasks(&["a.txt".to_string()], &[], &[]);
+        assert!(ZipCreatorController::new(config).start());
+
+        let file = File::open(root.join("archive.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let method = |archive: &mut zip::ZipArchive<File>, name| {
+            archive.by_name(name).unwrap().compression()
+        };
+        assert_eq!(method(&mut archive, "a.txt"),
+                   zip::CompressionMethod::Stored);
+        assert_eq!(method(&mut archive, "b.txt"),
+                   zip::CompressionMethod::Deflated);
+
+        // Other formats stream the entries into the new archive
+        tar.format = "tar".to_string();
+        tar.zip_path = root.join("archive.tar").to_string_lossy().to_string();
+        assert!(ZipCreatorController::new(tar.clone()).start());
+        fs::write(root.join("b.txt"), "changed").unwrap();
+        tar.select_tasks(&["a.txt".to_string()], &[], &[]);
+        assert!(ZipCreatorController::new(tar.clone()).start());
+
+        let mut reader = backend::open_reader(&tar).unwrap();
+        let mut contents = HashMap::new();
+        reader.read_entries(&mut |entry, content| {
+            let mut data = String::new();
+            content.read_to_string(&mut data)?;
+            contents.insert(entry.name.clone(), data);
+            Ok(())
+        }).unwrap();
+        assert_eq!(contents["b.txt"], "b".repeat(1000));
+        assert_eq!(contents["a.txt"], "a".repeat(1000));
+    }
+
+    #[test]
+    fn mirror_keeps_the_files_of_overlapping_tasks() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs/a.txt"), "a").unwrap();
+        fs::write(root.join("docs/b.txt"), "b").unwrap();
+        fs::write(root.join("note.txt"), "note").unwrap();
+        fs::write(root.join("todo.txt"), "todo").unwrap();
+
+        // "note" and "todo" are stored inside the directory of "docs"
+        let mut config = config(root, "continue", &[
+            ("docs", false), ("note.txt", false), ("todo.txt", false)
+        ]);
+        config.copy_tasks[1].target = "docs/".to_string();
+        config.copy_tasks[2].target = "docs/sub/".to_string();
+        config.format = "dir".to_string();
+        config.zip_path = root.join("mirror").to_string_lossy().to_string();
+        assert!(ZipCreatorController::new(config.clone()).start());
+
+        fs::remove_file(root.join("docs/b.txt")).unwrap();
+        for selected in [vec![], vec!["docs".to_string()]] {
+            let mut config = config.clone();
+            config.select_tasks(&selected, &[], &[]);
+            assert!(ZipCreatorController::new(config).start());
+
+            let mirror = root.join("mirror/docs");
+            assert!(mirror.join("a.txt").is_file());
+            assert!(!mirror.join("b.txt").exists());
+            assert!(mirror.join("note.txt").is_file());
+            assert!(mirror.join("sub/todo.txt").is_file());
+        }
+    }
+
+    #[test]
+    fn duplicate_entries_fail_the_task() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for directory in ["a", "b"] {
+            fs::create_dir(root.join(directory)).unwrap();
+            fs::write(root.join(directory).join("c.txt"), directory).unwrap();
+        }
+
+        // Both files are stored as "c.txt"
+        let sources = [("a/c.txt", true), ("b/c.txt", true)];
+        let mut creator = ZipCreatorController::new(
+            config(root, "continue", &sources)
+        );
+        assert!(!creator.start());
+
+        let file = File::open(root.join("archive.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        assert_eq!(archive.len(), 1);
+        let mut read = String::new();
+        archive.by_name("c.txt").unwrap().read_to_string(&mut read).unwrap();
+        assert_eq!(read, "a");
+    }
+}
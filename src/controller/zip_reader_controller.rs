@@ -1,12 +1,17 @@
 use std::fs;
-use std::io::{self, BufReader};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
 use colored::*;
-use fs_extra::{copy_items, dir::*};
-use zip::ZipArchive;
 
 
 use crate::{model::config::Config, model::sync_task::SyncTask};
+use crate::backend::{self, ArchiveFormat};
+use crate::model::archive_entry::EntryKind;
+use crate::model::backup::Backup;
+use crate::model::file_metadata::FileMetadata;
+use crate::model::journal::{Journal, JournalStep};
+use crate::model::manifest::{Manifest, MANIFEST_PATH};
+use crate::model::path_mapping::PathMapping;
 
 
 /// The ZipReaderController is responsible for reading the ZIP file and
@@ -23,7 +28,10 @@ pub struct ZipReaderController {
     sync_tasks: Vec<SyncTask>,
 
     /// The output folder where the ZIP file was extracted to.
-    outputfolder: Option<String>
+    outputfolder: Option<String>,
+
+    /// The path in the archive which is restored (all paths if `None`).
+    only_path: Option<String>
 }
 
 impl ZipReaderController {
@@ -31,159 +39,135 @@ impl ZipReaderController {
         ZipReaderController {
             config,
             sync_tasks: Vec::new(),
-            outputfolder: None
+            outputfolder: None,
+            only_path: None
         }
     }
 
+    /// Restricts the extraction to a single path in the archive (a file or a
+    /// directory of one of the copy tasks).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path in the archive.
+    pub fn restrict_to_path(&mut self, path: &str) {
+        self.only_path = Some(path.trim_matches('/').to_string());
+    }
+
     /// Starts the ZIP reader controller.
     ///
     /// This method extracts the ZIP file to a folder, creates the sync tasks
     /// based on the configuration, checks the ZIP archive for new files that
-    /// are not handled by the config, and syncs the files.
-    pub fn start(&mut self) {
+    /// are not handled by the config, and syncs the files. If an extraction
+    /// was interrupted, it is only recovered (nothing else is extracted).
+    /// If a required task is missing in the archive, nothing is synced
+    /// unless the `on_error` policy is "continue".
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the archive was extracted and no required task
+    ///   is missing.
+    pub fn start(&mut self) -> bool {
+        let policy = match self.config.error_policy() {
+            Ok(policy) => policy.to_string(),
+            Err(e) => {
+                println!("{}", format!("!!! {}", e).red().bold());
+                return false;
+            }
+        };
+        if let Some(recovered) = self.recover_interrupted_run() {
+            return recovered;
+        }
         if let Err(e) = self.extract_zip_to_folder() {
             eprintln!("Error extracting ZIP file: {}", e);
-            return;
+            return false;
         }
-        self.create_sync_tasks_from_config();
+        let missing = self.create_sync_tasks_from_config();
         self.check_zip_for_new_files();
-        self.sync_files();
+        if missing.is_empty() {
+            return self.sync_files();
+        }
+
+        println!("{}", format!(
+            "!!! Required task(s) not found in the archive: {}",
+            missing.join(", ")
+        ).red().bold());
+        if policy == "continue" {
+            self.sync_files();
+        } else {
+            println!("{}", "!!! No files were synced".red().bold());
+        }
+        false
     }
 
     /// Creates the sync tasks based on the configuration.
     /// The sync tasks are used to compare the files in the ZIP archive
-    /// with the files in the extract paths. If no zip path (= target path)
-    /// is given, the last path component of the extract path (= source path)
-    /// is used ("/aa/bb/cc" -> "cc").
+    /// with the files in the extract paths. Both paths are taken from the
+    /// `PathMapping` of the task, which is also used to create the archive;
+    /// whether a task is a file or a directory is read from the extracted
+    /// archive. Tasks which aren't in the archive are skipped.
     /// The sync tasks are stored in the `sync_tasks` vector.
-    fn create_sync_tasks_from_config(&mut self) {
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - The sources of the required tasks which aren't in
+    ///   the archive (only checked if all paths are restored).
+    fn create_sync_tasks_from_config(&mut self) -> Vec<String> {
+        let output_folder = PathBuf::from(
+            self.outputfolder.clone().unwrap_or_default()
+        );
+        let mut missing = Vec::new();
+
         // Loop tasks from config
         for task in &self.config.copy_tasks {
-            let mut zip_path: String;
-
-            // Path to extract the file to (= former source path)
-            let mut extract_path: String = task.source.clone();
-
-            // Get the path of the item in the zip path (= former target path)
-            if task.target.clone().is_empty() {
-                // If the zip path is empty, get directory from the extract path
-                zip_path = Self::get_last_path_component(&extract_path)
-                           .unwrap_or_else(
-                               || String::from("default_path")
-                           );
-
-                if extract_path.ends_with("/") {
-                    extract_path = Self::remove_last_path_component(
-                                       &extract_path)
-                                   .unwrap_or_else(
-                                       || String::from("default_path")
-                                   );
+            let mapping = PathMapping::from_archive(task, |zip_path| {
+                fs::symlink_metadata(output_folder.join(zip_path)).ok()
+                    .map(|metadata| metadata.is_dir())
+            });
+            let Some(mapping) = mapping else {
+                println!("{}", format!(
+                    "Skipping {}: not found in the archive", task.source
+                ).red().bold());
+                if task.is_required() && self.only_path.is_none() {
+                    missing.push(task.source.clone());
                 }
-            } else {
-                zip_path = task.target.clone();
-            }
-
-            // Remove leading slash from zip path
-            if zip_path.starts_with("/") {
-                zip_path = zip_path[1..].to_string();
-            }
-
-            // Check if zip_path is a folder but extract_path is a file
-            let extract_path_obj = Path::new(&extract_path);
+                continue;
+            };
 
-            if Path::new(&zip_path).to_string_lossy().ends_with('/')
-            && !Path::new(&extract_path).to_string_lossy().ends_with('/') {
-                if let Some(file_name) = extract_path_obj.file_name() {
-                    zip_path.push_str(&file_name.to_string_lossy());
+            // Only the restricted path of the task (if any)
+            let (zip_path, extract_path) = match &self.only_path {
+                None => (mapping.archive_path, mapping.local_path),
+                Some(path) => match mapping.local_path_of(path) {
+                    Some(local_path) => (path.clone(), local_path),
+                    None => continue
                 }
-            }
+            };
 
             // Create new sync task
             let sync_task: SyncTask = SyncTask {
-                zip_path: zip_path,
-                extract_path: extract_path,
+                zip_path,
+                extract_path: extract_path.to_string_lossy().to_string(),
                 zip_date: None,
                 extract_date: None
             };
             self.sync_tasks.push(sync_task);
         }
-    }
-
-    /// Returns the last path component of a path string.
-    ///
-    /// If the path ends with a slash, remove it to get the last path component
-    /// (e.g. "/aa/bb/cc/" -> "cc").
-    /// If the path is just a slash, return it as is.
-    /// If the path is empty, return a default value.
-    /// Otherwise, return the last path component.
-    ///
-    /// # Arguments
-    ///
-    /// * `path_str` - The path string to extract the last path component from.
-    ///
-    /// # Returns
-    ///
-    /// The last path component of the path string.
-    fn get_last_path_component(path_str: &str) -> Option<String> {
-        let normalized_path = if path_str.ends_with('/') && path_str.len() > 1 {
-            &path_str[..path_str.len() - 1]
-        } else {
-            path_str
-        };
-
-        Path::new(normalized_path)
-            .file_name()
-            .and_then(|os_str| os_str.to_str())
-            .map(|s| {
-                if path_str.ends_with('/') {
-                    format!("{}/", s)
-                } else {
-                    s.to_string()
-                }
-            })
-    }
 
-    /// Returns the path string without the last path component.
-    ///
-    /// If the path ends with a slash, remove it to get the parent directory
-    /// (e.g. "/aa/bb/cc/" -> "/aa/bb/").
-    /// If the path is just a slash, return it as is.
-    /// If the path is empty, return a default value.
-    /// Otherwise, return the path without the last path component.
-    ///
-    /// # Arguments
-    ///
-    /// * `path_str` - The path string to remove the last path component from.
-    ///
-    /// # Returns
-    ///
-    /// The path string without the last path component.
-    fn remove_last_path_component(path_str: &str) -> Option<String> {
-        let path = Path::new(path_str);
-
-        // If the path ends with a slash, remove it to get the parent directory
-        let clean_path = if path_str.ends_with('/') && path_str.len() > 1 {
-            Path::new(&path_str[..path_str.len() - 1])
-        } else {
-            path
-        };
-
-        // Get the parent directory of the path
-        clean_path.parent().map(|p| {
-            let result = p.to_path_buf();
-
-            // Ensure trailing slash
-            let mut result_str = result.to_string_lossy().to_string();
-            if !result_str.ends_with('/') {
-                result_str.push('/');
-            }
-            result_str
-        })
+        if let Some(path) = &self.only_path
+        && self.sync_tasks.is_empty() {
+            println!("{}", format!(
+                "!!! '{}' doesn't belong to any copy task", path
+            ).red().bold());
+        }
+        missing
     }
 
-    /// Extracts the ZIP file into a folder next to the ZIP file.
-    /// The folder will have the same name as the ZIP file
-    /// (without the extension).
+    /// Extracts the archive into a folder next to the archive.
+    /// The folder will have the same name as the archive
+    /// (without the extension, e.g. "backup.tar.zst" -> "backup").
+    /// Entries with absolute paths or ".." components are skipped, as are
+    /// the entries of copy tasks which weren't selected.
+    /// A directory mirror ("dir" format) is used as it is.
     ///
     /// # Returns
     ///
@@ -192,6 +176,19 @@ impl ZipReaderController {
         let zip_file_path = &self.config.zip_path;
         let zip_path = Path::new(zip_file_path);
 
+        // The mirror already is an extracted folder
+        let format = ArchiveFormat::from_config(&self.config)?;
+        if format == ArchiveFormat::Dir {
+            if !zip_path.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Directory '{}' not found", zip_file_path),
+                ));
+            }
+            self.outputfolder = Some(zip_file_path.clone());
+            return self.check_manifest(zip_path);
+        }
+
         // Ensure the ZIP file exists
         if !zip_path.exists() || !zip_path.is_file() {
             return Err(io::Error::new(
@@ -204,32 +201,89 @@ impl ZipReaderController {
         let output_folder = zip_path
             .parent()
             .unwrap_or_else(|| Path::new("."))
-            .join(zip_path.file_stem().unwrap_or_default());
+            .join(format.archive_stem(zip_path));
 
         // Create the output folder if it doesn't exist
         if !output_folder.exists() {
             fs::create_dir_all(&output_folder)?;
         }
 
-        // Open the ZIP file
-        let file = fs::File::open(zip_file_path)?;
-        let reader = BufReader::new(file);
-        let mut archive = ZipArchive::new(reader)?;
+        // Open the archive
+        let mut reader = backend::open_reader(&self.config)?;
 
-        // Extract each file in the ZIP archive
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let out_path = output_folder.join(file.name());
+        // Extract each entry of the archive. Symlinks are created after all
+        // files and directories, so no entry is written through a link.
+        let mut directories = Vec::new();
+        let mut symlinks = Vec::new();
+        reader.read_entries(&mut |entry, content| {
+            if !self.is_selected(&entry.name) {
+                return Ok(());
+            }
+            let out_path = output_folder.join(&entry.name);
+            if !entry.has_safe_name()
+            || Self::has_symlink_parent(&out_path, &output_folder) {
+                println!("{}", format!(
+                    "!!! Skipping entry outside of the output folder: {}",
+                    entry.name
+                ).yellow().bold());
+                return Ok(());
+            }
 
-            if file.is_dir() {
-                fs::create_dir_all(&out_path)?;
-            } else {
-                if let Some(parent) = out_path.parent() {
-                    fs::create_dir_all(parent)?;
+            match &entry.kind {
+                EntryKind::Directory => {
+                    // A previous run might have made it read-only, its
+                    // mode is applied again after its content
+                    fs::create_dir_all(&out_path)?;
+                    FileMetadata::make_writable(&out_path)?;
+                    directories.push((out_path, entry.metadata));
+                },
+                EntryKind::Symlink(target) => {
+                    symlinks.push((target.clone(), out_path));
+                },
+                EntryKind::File => {
+                    if let Some(parent) = out_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+
+                    // Remove files of a previous run, they might be read-only
+                    if out_path.is_file() || out_path.is_symlink() {
+                        fs::remove_file(&out_path)?;
+                    }
+
+                    let mut outfile = fs::File::create(&out_path)?;
+                    io::copy(content, &mut outfile)?;
+                    entry.metadata.apply(&out_path)?;
                 }
-                let mut outfile = fs::File::create(&out_path)?;
-                io::copy(&mut file, &mut outfile)?;
             }
+
+            Ok(())
+        })?;
+
+        for (target, link) in symlinks {
+            let conflict = if Self::has_symlink_parent(&link, &output_folder) {
+                Some("inside of another symlink")
+            } else if link.is_dir() && !link.is_symlink() {
+                Some("a directory with the same path exists")
+            } else {
+                None
+            };
+            if let Some(conflict) = conflict {
+                println!("{}", format!(
+                    "!!! Skipping symlink ({}): {}", conflict, link.display()
+                ).yellow().bold());
+                continue;
+            }
+            if let Some(parent) = link.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Self::create_symlink(&target, &link, &output_folder, None)?;
+        }
+
+        // Apply the metadata of the directories after their content was
+        // extracted (deepest directories first)
+        directories.sort_by(|a, b| b.0.cmp(&a.0));
+        for (path, metadata) in directories {
+            metadata.apply(&path)?;
         }
 
         println!(
@@ -240,84 +294,632 @@ impl ZipReaderController {
 
         // Set the output folder
         self.outputfolder = Some(output_folder.to_string_lossy().to_string());
+        self.check_manifest(&output_folder)?;
 
         Ok(())
     }
 
+    /// Checks if an entry of the archive belongs to the selected copy tasks
+    /// (and to the restricted path, if any). Without a selection, all
+    /// entries are selected; the manifest is always selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The path of the entry in the archive.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the entry is extracted.
+    fn is_selected(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('/');
+        if name == MANIFEST_PATH {
+            return true;
+        }
+
+        if let Some(path) = &self.only_path
+        && name != path && !name.starts_with(&format!("{}/", path)) {
+            return false;
+        }
+        if self.config.skipped_tasks.is_empty() {
+            return true;
+        }
+
+        // The kind of the task is only known after the extraction
+        self.config.copy_tasks.iter().any(|task| {
+            [false, true].into_iter().any(|is_dir| {
+                PathMapping::new(task, is_dir).local_path_of(name).is_some()
+            })
+        })
+    }
+
+    /// Reads the manifest from the extracted ZIP archive (if present) and
+    /// compares the hash of every extracted file with the hash stored in the
+    /// manifest. Mismatches are printed together with the task that produced
+    /// the entry, and nothing is synced.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_folder` - The folder the ZIP file was extracted to.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If all entries match the manifest (or there is none).
+    /// * `Err(Error)` - If the manifest cannot be read or an entry doesn't
+    ///   match it.
+    fn check_manifest(&self, output_folder: &Path) -> io::Result<()> {
+        let manifest_path = output_folder.join(MANIFEST_PATH);
+        if !manifest_path.is_file() {
+            return Ok(());
+        }
+
+        let file = fs::File::open(&manifest_path)?;
+        let manifest: Manifest = serde_json::from_reader(BufReader::new(file))?;
+        let entries: Vec<_> = manifest.entries.iter()
+            .filter(|entry| self.is_selected(&entry.path))
+            .collect();
+        println!(
+            "Checking {} entries against the manifest (ZipSync {}, created \
+             {})",
+            entries.len(), manifest.tool_version, manifest.created_at
+        );
+
+        let mut mismatches = 0;
+        for entry in entries {
+            let extracted = output_folder.join(&entry.path);
+            let intact = match fs::read(&extracted) {
+                Ok(content) => manifest.hash(&content) == entry.hash,
+                Err(_) => false
+            };
+
+            if !intact {
+                mismatches += 1;
+                println!("{}", format!(
+                    "!!! Entry doesn't match the manifest: '{}' (task: '{}')",
+                    entry.path, entry.task
+                ).red().bold());
+            }
+        }
+
+        if mismatches > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} entries don't match the manifest", mismatches)
+            ));
+        }
+
+        println!("{}", "Manifest check successful.".green().bold());
+        Ok(())
+    }
+
     /// Checks the ZIP archive for new files that are not handled by the config.
     fn check_zip_for_new_files(&self) {
         // TODO: Implement this method
     }
 
     /// Gathers metadata for the files in the ZIP archive and the extract paths.
-    fn sync_files(&self) {
+    /// Local files are moved into a backup before they are replaced (unless
+    /// `backup` is "no"). The steps are written to a journal first and every
+    /// completed step is marked, so an interrupted run can be rolled forward
+    /// or back by the next run. Afterwards, only the newest `backup_keep`
+    /// backups are kept.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all files were synced.
+    fn sync_files(&self) -> bool {
         println!("{}", self.outputfolder.as_ref().unwrap());
 
-        // Loop over all sync tasks
+        // Plan the steps (only the entries which are in the zip)
+        let mut steps = Vec::new();
         for task in &self.sync_tasks {
-            // println!("Fange an mit: {:#?}", task);
-
-            // Check if the file is in the zip
             let zip_path = Path::new(&self.outputfolder.as_ref().unwrap())
                         .join(Path::new(&task.zip_path));
-            let mut extract_path = Path::new(&task.extract_path).to_path_buf();
+            let extract_path = Path::new(&task.extract_path).to_path_buf();
 
-            // Check if the file is in the zip
-            if zip_path.exists() {
-                println!("{}", format!(
-                    "{}    ---->    {}",
-                    &zip_path.display(), &extract_path.display()
-                ).green().bold());
+            if zip_path.exists() || zip_path.is_symlink() {
+                steps.push(JournalStep { from: zip_path, to: extract_path });
             } else {
                 println!("{}", format!(
                     "Skipping {}    ---->    {}",
                     &zip_path.display(), &extract_path.display()
                 ).red().bold());
-                continue;
             }
+        }
+        if steps.is_empty() {
+            return true;
+        }
 
-            // Directory or file?
-            if zip_path.is_file() {
-                // If extract_path is an existing directory, append the
-                // filename to it
-                if extract_path.exists() && extract_path.is_dir() {
-                    if let Some(file_name) = zip_path.file_name() {
-                        extract_path = extract_path.join(file_name);
-                    }
-                }
+        let root = self.config.backup_root();
+        let mut backup = (self.config.backup != "no")
+            .then(|| Backup::new(&root));
+        let result = Journal::begin(
+            &root, backup.as_ref().map(|backup| backup.directory.clone()),
+            steps.clone()
+        ).and_then(|journal| Self::run_steps(journal, &mut backup));
+
+        if let Some(backup) = &backup
+        && !backup.entries.is_empty() {
+            println!("{}", format!(
+                "Replaced files were saved to '{}' (zipsync undo restores \
+                 them)", backup.directory.display()
+            ).bold());
+        }
 
-                // Create target directory if it doesn't exist
-                if let Some(parent) = extract_path.parent() {
-                    fs::create_dir_all(parent)
-                    .expect("Couldn't create target directory!");
-                }
+        if let Err(e) = result {
+            println!("{}", format!(
+                "!!! Extraction interrupted: {} (the next run can roll it \
+                 forward or back)", e
+            ).red().bold());
+            return false;
+        }
 
-                // Set copy options with overwrite enabled
-                let mut file_options = fs_extra::file::CopyOptions::new();
-                file_options.overwrite = true;  // Enable overwrite
-
-                // Datei kopieren
-                fs_extra::file::copy(&zip_path, &extract_path, &file_options)
-                    .expect("File could not be copied!");
-            } else if zip_path.is_dir() {
-                // Create target directory if it doesn't exist
-                fs::create_dir_all(&extract_path)
-                .expect("Couldn't create target directory!");
-
-                // Set directory copy options
-                let mut options = CopyOptions::new();
-                options.overwrite = true;
-                options.copy_inside = true;
-
-                // Copy directory
-                let from_paths = vec![zip_path.clone()];
-                copy_items(&from_paths, &extract_path, &options)
-                    .expect("Directory could not be copied!");
+        if let Some(keep) = self.config.backup_keep
+        && let Err(e) = Backup::prune(&root, keep) {
+            println!("{}", format!(
+                "Old backups could not be deleted: {}", e
+            ).yellow().bold());
+        }
+        true
+    }
+
+    /// Executes the pending steps of a journal and removes the journal when
+    /// all steps are completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `journal` - The journal of the extraction.
+    /// * `backup` - The backup the replaced files are moved to (if any).
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` indicating the success of the operation.
+    fn run_steps(mut journal: Journal, backup: &mut Option<Backup>)
+    -> io::Result<()> {
+        for (index, step) in journal.pending() {
+            println!("{}", format!(
+                "{}    ---->    {}", step.from.display(), step.to.display()
+            ).green().bold());
+            Self::sync_path(&step.from, &step.to, backup)?;
+            journal.complete(index)?;
+        }
+        journal.finish()
+    }
+
+    /// Copies an entry of the extracted archive to its local path.
+    ///
+    /// # Arguments
+    ///
+    /// * `zip_path` - The path of the entry in the extracted archive.
+    /// * `extract_path` - The local path.
+    /// * `backup` - The backup the replaced files are moved to (if any).
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` indicating the success of the operation.
+    fn sync_path(
+        zip_path: &Path, extract_path: &Path, backup: &mut Option<Backup>
+    ) -> io::Result<()> {
+        // Symlink, directory or file?
+        if zip_path.is_symlink() {
+            // Links must not point outside the parent directory
+            let root = extract_path.parent().unwrap_or(Path::new("/"))
+                                   .to_path_buf();
+            fs::create_dir_all(&root)?;
+            Self::copy_symlink(zip_path, extract_path, &root, backup.as_mut())
+        } else if zip_path.is_file() {
+            // Create target directory if it doesn't exist
+            if let Some(parent) = extract_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            // Copy file (overwrites existing files)
+            Self::copy_file(zip_path, extract_path, backup.as_mut())
+        } else if zip_path.is_dir() {
+            // Copy directory to the target directory (creates it if it
+            // doesn't exist)
+            Self::copy_directory(zip_path, extract_path, extract_path, backup)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Path is neither a dir nor a file: {}",
+                        zip_path.display())
+            ))
+        }
+    }
+
+    /// Checks for an extraction which was interrupted and asks whether it is
+    /// rolled forward (the remaining steps are executed) or rolled back (the
+    /// replaced files are restored from the backup).
+    ///
+    /// # Returns
+    ///
+    /// * `None` - If no extraction was interrupted.
+    /// * `Some(bool)` - True if the interrupted extraction was recovered;
+    ///   false if the recovery failed or was aborted.
+    fn recover_interrupted_run(&self) -> Option<bool> {
+        let root = self.config.backup_root();
+        let journal = match Journal::open(&root) {
+            Ok(journal) => journal?,
+            Err(e) => {
+                println!("{}", format!("!!! {}", e).red().bold());
+                return Some(false);
+            }
+        };
+
+        let completed = journal.completed.iter().filter(|done| **done).count();
+        println!("{}", format!(
+            "!!! The previous extraction was interrupted after {} of {} \
+             step(s)", completed, journal.steps.len()
+        ).yellow().bold());
+
+        let question = match journal.backup {
+            Some(_) => "Roll [f]orward, roll [b]ack or [a]bort?",
+            None => "Roll [f]orward or [a]bort (no backup to roll back)?"
+        };
+        let answer = match Self::ask(question) {
+            Ok(answer) => answer,
+            Err(e) => {
+                println!("{}", format!("!!! {}", e).red().bold());
+                return Some(false);
+            }
+        };
+
+        let result = match (answer.as_str(), &journal.backup) {
+            ("f" | "forward", _) => journal.backup.as_deref()
+                .map(Backup::open)
+                .transpose()
+                .and_then(|mut backup| Self::run_steps(journal, &mut backup)),
+            ("b" | "back", Some(directory)) => Backup::open(directory)
+                .and_then(|backup| backup.restore(false))
+                .and_then(|_count| journal.finish()),
+            _ => {
+                println!("Aborted, the journal is kept");
+                return Some(false);
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                let message = "Interrupted extraction recovered";
+                println!("{}", message.green().bold());
+                Some(true)
+            },
+            Err(e) => {
+                println!("{}", format!(
+                    "!!! Recovery failed: {}", e
+                ).red().bold());
+                Some(false)
+            }
+        }
+    }
+
+    /// Asks a question on the terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - The question.
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` with the lowercase answer.
+    fn ask(question: &str) -> io::Result<String> {
+        print!("{} ", question);
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        Ok(answer.trim().to_lowercase())
+    }
+
+    /// Copies a file and preserves its modification time and permissions.
+    /// An existing file at the target path is overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The path of the file to copy.
+    /// * `to` - The target path of the file.
+    /// * `backup` - The backup the existing file is moved to (if any).
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` indicating the success of the operation.
+    fn copy_file(from: &Path, to: &Path, mut backup: Option<&mut Backup>)
+    -> io::Result<()> {
+        if let Some(backup) = backup.as_deref_mut() {
+            backup.save(to, Some(from))?;
+        }
+
+        // Replace symlinks instead of writing to their target, and existing
+        // files which might be read-only (e.g. mode 0400)
+        if to.is_symlink() || to.is_file() {
+            fs::remove_file(to)?;
+        }
+
+        fs::copy(from, to)?;
+        FileMetadata::from_path(from)?.apply(to)?;
+        match backup {
+            Some(backup) => backup.mark_written(to),
+            None => Ok(())
+        }
+    }
+
+    /// Recursively copies a directory (including empty subdirectories) and
+    /// preserves the modification times and permissions of the files and
+    /// directories. Existing files are overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The path of the directory to copy.
+    /// * `to` - The target path of the directory.
+    /// * `root` - The top-level target directory; symlinks must not point
+    ///   outside of it.
+    /// * `backup` - The backup the existing files are moved to (if any).
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` indicating the success of the operation.
+    fn copy_directory(
+        from: &Path, to: &Path, root: &Path, backup: &mut Option<Backup>
+    ) -> io::Result<()> {
+        // An existing directory might be read-only (e.g. mode 0555), its
+        // mode is applied again after its content
+        fs::create_dir_all(to)?;
+        FileMetadata::make_writable(to)?;
+
+        for entry in fs::read_dir(from)? {
+            let path = entry?.path();
+            let target = to.join(path.file_name().unwrap_or_default());
+
+            if path.is_symlink() {
+                Self::copy_symlink(&path, &target, root, backup.as_mut())?;
+            } else if path.is_dir() {
+                Self::copy_directory(&path, &target, root, backup)?;
             } else {
-                eprintln!("{}", format!(
-                    "Path is neither a dir nor a file: {}", zip_path.display()
-                ).yellow().bold());
+                Self::copy_file(&path, &target, backup.as_mut())?;
             }
         }
+
+        // Applied last, copying the content changes the modification time
+        FileMetadata::from_path(from)?.apply(to)
+    }
+
+    /// Copies a symbolic link (not its target).
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The path of the symlink to copy.
+    /// * `to` - The target path of the symlink.
+    /// * `root` - The directory the symlink must not point outside of.
+    /// * `backup` - The backup an existing file is moved to (if any).
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` indicating the success of the operation.
+    fn copy_symlink(
+        from: &Path, to: &Path, root: &Path, backup: Option<&mut Backup>
+    ) -> io::Result<()> {
+        let target = fs::read_link(from)?;
+        Self::create_symlink(&target, to, root, backup)
+    }
+
+    /// Creates a symbolic link. Existing files at the path of the link are
+    /// replaced. Links which point outside of `root` are not created.
+    ///
+    /// The parent of the link is resolved on disk, so links created before
+    /// cannot be used to leave `root`. The target is checked lexically and
+    /// may only contain ".." at its start: otherwise, ".." after another
+    /// link in the target would leave the directory the link points to,
+    /// which the lexical check doesn't see.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target path of the symlink.
+    /// * `link` - The path of the symlink.
+    /// * `root` - The directory the symlink must not point outside of.
+    /// * `backup` - The backup an existing file is moved to (if any).
+    ///
+    /// # Returns
+    ///
+    /// An `io::Result` indicating the success of the operation.
+    fn create_symlink(
+        target: &Path, link: &Path, root: &Path,
+        mut backup: Option<&mut Backup>
+    ) -> io::Result<()> {
+        let parent = fs::canonicalize(
+            link.parent().unwrap_or(Path::new("."))
+        )?;
+        let inside_root = Self::normalize_path(&parent.join(target))
+            .starts_with(fs::canonicalize(root)?);
+        let descends = target.components()
+            .skip_while(|component| {
+                matches!(component, Component::ParentDir | Component::CurDir)
+            })
+            .all(|component| component != Component::ParentDir);
+        if !inside_root || !descends {
+            println!("{}", format!(
+                "!!! Skipping symlink pointing outside of '{}': {} -> {}",
+                root.display(), link.display(), target.display()
+            ).yellow().bold());
+            return Ok(());
+        }
+
+        if let Some(backup) = backup.as_deref_mut() {
+            backup.save(link, None)?;
+        }
+        if link.is_symlink() || link.is_file() {
+            fs::remove_file(link)?;
+        }
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target, link)?;
+        #[cfg(not(unix))]
+        println!("{}", format!(
+            "!!! Symlinks are not supported on this platform: {}",
+            link.display()
+        ).yellow().bold());
+
+        match backup {
+            Some(backup) => backup.mark_written(link),
+            None => Ok(())
+        }
     }
-}
\ No newline at end of file
+
+    /// Checks if a path inside of a root directory has a symlink among its
+    /// existing parent directories (below the root).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path inside of the root.
+    /// * `root` - The root directory.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if a parent directory of the path is a symlink.
+    fn has_symlink_parent(path: &Path, root: &Path) -> bool {
+        path.ancestors()
+            .skip(1)
+            .take_while(|parent| *parent != root)
+            .any(Path::is_symlink)
+    }
+
+    /// Normalizes a path lexically by resolving "." and ".." components
+    /// (without accessing the file system).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to normalize.
+    ///
+    /// # Returns
+    ///
+    /// The normalized path.
+    fn normalize_path(path: &Path) -> PathBuf {
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {},
+                Component::ParentDir => { normalized.pop(); },
+                other => normalized.push(other)
+            }
+        }
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use super::*;
+    use crate::controller::zip_creator_controller::ZipCreatorController;
+
+    #[test]
+    fn entries_stay_inside_of_the_output_folder() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let zip_path = root.join("archive.zip");
+
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = SimpleFileOptions::default();
+        for (link, target) in [("a", "."), ("a/l", ".."), ("b", "."),
+                               ("b/l", ".."), ("c", "b/..")] {
+            zip.add_symlink(link, target, options).unwrap();
+        }
+        zip.start_file("a/l/x", options).unwrap();
+        zip.write_all(b"x").unwrap();
+        zip.finish().unwrap();
+
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "zip_path": zip_path,
+            "direction": "from_zip",
+            "confirm_new": "no",
+            "confirm_overwrite": "no",
+            "confirm_delete": "no",
+            "copy_tasks": []
+        })).unwrap();
+        ZipReaderController::new(config).extract_zip_to_folder().unwrap();
+
+        let output_folder = root.join("archive");
+        assert!(!root.join("x").exists());
+        assert!(!output_folder.join("x").exists());
+        assert!(!output_folder.join("l").exists());
+        assert!(output_folder.join("a/l/x").is_file());
+        assert!(output_folder.join("b").is_symlink());
+        assert!(!output_folder.join("c").is_symlink());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_files_are_overwritten() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let (from, to) = (temp.path().join("from"), temp.path().join("to"));
+        for content in ["old", "new"] {
+            fs::write(&from, content).unwrap();
+            fs::set_permissions(&from, fs::Permissions::from_mode(0o400))
+                .unwrap();
+            ZipReaderController::copy_file(&from, &to, None).unwrap();
+            fs::remove_file(&from).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&to).unwrap(), "new");
+        let mode = fs::metadata(&to).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o400);
+    }
+
+    #[test]
+    fn tampered_entries_are_not_synced() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let tasks: Vec<_> = ["a.txt", "b.txt"].iter().map(|name| {
+            serde_json::json!({
+                "source": root.join(name),
+                "description": name,
+                "target": ""
+            })
+        }).collect();
+        let mut config: Config = serde_json::from_value(serde_json::json!({
+            "zip_path": root.join("archive.zip"),
+            "direction": "to_zip",
+            "confirm_new": "no",
+            "confirm_overwrite": "no",
+            "confirm_delete": "no",
+            "manifest": "yes",
+            "copy_tasks": tasks
+        })).unwrap();
+        assert!(ZipCreatorController::new(config.clone()).start());
+
+        // Replace "a.txt" with a valid entry of a different content
+        let zip_path = root.join("archive.zip");
+        let mut previous = zip::ZipArchive::new(
+            fs::File::open(&zip_path).unwrap()
+        ).unwrap();
+        let tampered_path = root.join("tampered.zip");
+        let mut zip = zip::ZipWriter::new(
+            fs::File::create(&tampered_path).unwrap()
+        );
+        for i in 0..previous.len() {
+            let entry = previous.by_index_raw(i).unwrap();
+            if entry.name() == "a.txt" {
+                drop(entry);
+                zip.start_file("a.txt", SimpleFileOptions::default())
+                    .unwrap();
+                zip.write_all(b"tampered").unwrap();
+            } else {
+                zip.raw_copy_file(entry).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+        fs::rename(&tampered_path, &zip_path).unwrap();
+
+        fs::write(root.join("a.txt"), "local a").unwrap();
+        fs::write(root.join("b.txt"), "local b").unwrap();
+        config.direction = "from_zip".to_string();
+        assert!(!ZipReaderController::new(config).start());
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "local a");
+        assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "local b");
+    }
+}
@@ -0,0 +1,406 @@
+use std::fs::{self, File};
+use std::io::{self, Error, Write};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use sha2::{Digest, Sha256};
+use colored::*;
+
+use crate::backend;
+use crate::model::config::Config;
+use crate::model::archive_entry::{ArchiveEntry, EntryKind};
+use crate::model::manifest::MANIFEST_PATH;
+use crate::controller::zip_creator_controller::ZipCreatorController;
+
+
+/// The ZipVerifyController checks the content of an existing archive against
+/// the source files on disk. Every entry is decompressed, its CRC32 is checked
+/// (ZIP only) and its size and SHA-256 hash are compared with the source file.
+pub struct ZipVerifyController {
+    /// The configuration object which holds the settings for the application
+    /// and the copy tasks.
+    config: Config,
+
+    /// The expected ZIP entries (path in the ZIP -> entry).
+    expected_entries: BTreeMap<String, ArchiveEntry>,
+
+    /// The verification result for every entry (path in the ZIP -> status).
+    results: BTreeMap<String, EntryStatus>
+}
+
+impl ZipVerifyController {
+    pub fn new(config: Config) -> ZipVerifyController {
+        ZipVerifyController {
+            config,
+            expected_entries: BTreeMap::new(),
+            results: BTreeMap::new()
+        }
+    }
+
+    /// Starts the verification process and prints a report.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all entries are intact, false otherwise.
+    pub fn start(&mut self) -> bool {
+        println!("Verifying: {}", self.config.zip_path);
+        self.collect_expected_entries();
+
+        if let Err(e) = self.verify_zip() {
+            println!(
+                "{}",
+                format!("Error while verifying the ZIP file: {}", e)
+                    .red().bold()
+            );
+            return false;
+        }
+
+        self.display_report()
+    }
+
+    /// Collects the expected ZIP entries of all copy tasks. The entries are
+    /// determined with the same rules that are used during creation.
+    fn collect_expected_entries(&mut self) {
+        for task in &self.config.copy_tasks {
+            match ZipCreatorController::collect_entries(task) {
+                Ok(entries) => {
+                    for entry in entries {
+                        // Directory entries are stored with a trailing slash
+                        let name = if entry.kind == EntryKind::Directory {
+                            format!("{}/", entry.zip_path)
+                        } else {
+                            entry.zip_path.clone()
+                        };
+                        self.expected_entries.insert(name, entry);
+                    }
+                },
+                Err(error_type) => {
+                    println!(
+                        "{}",
+                        format!("Ignoring during verification: '{}' ({})",
+                                task.source, error_type.message())
+                            .yellow().bold()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Decompresses every entry of the archive and compares it with the
+    /// corresponding source file. The result is stored in `results`.
+    fn verify_zip(&mut self) -> Result<(), Error> {
+        let mut reader = backend::open_reader(&self.config)?;
+        let mut found_entries = HashSet::new();
+
+        // The directory of the manifest isn't part of any copy task
+        let manifest_dir = Path::new(MANIFEST_PATH).parent()
+            .unwrap_or(Path::new(MANIFEST_PATH));
+
+        reader.read_entries(&mut |entry, content| {
+            if Path::new(&entry.name).starts_with(manifest_dir) {
+                return Ok(());
+            }
+
+            // Directory entries are compared with a trailing slash
+            let name = if entry.kind == EntryKind::Directory {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            found_entries.insert(name.clone());
+
+            // Directories which are not part of a copy task are parents of
+            // files and don't need to be checked
+            if entry.kind == EntryKind::Directory {
+                if self.expected_entries.contains_key(&name) {
+                    self.results.insert(name, EntryStatus::Intact);
+                }
+                return Ok(());
+            }
+
+            // Entries which aren't produced by any copy task. Entries of
+            // tasks which weren't selected are kept and not checked.
+            let expected = match self.expected_entries.get(&name) {
+                Some(expected) => expected.clone(),
+                None => {
+                    if ZipCreatorController::skipped_task_of(
+                        &self.config, &name
+                    ).is_none() {
+                        self.results.insert(name, EntryStatus::Extra);
+                    }
+                    return Ok(());
+                }
+            };
+
+            let mut digest = EntryDigest::new();
+            let status = match io::copy(content, &mut digest) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    EntryStatus::Truncated
+                },
+                Err(e) => EntryStatus::Corrupt(e.to_string()),
+                Ok(size) if !matches!(entry.kind, EntryKind::Symlink(_))
+                         && size != entry.size => EntryStatus::Truncated,
+                Ok(_) if entry.crc32.is_some_and(|crc| crc != digest.crc32())
+                => {
+                    EntryStatus::Corrupt("CRC32 mismatch".to_string())
+                },
+                Ok(size) => Self::compare_with_source(
+                    &expected, size, digest.sha256()
+                )
+            };
+            self.results.insert(name, status);
+            Ok(())
+        })?;
+
+        // Expected entries which are not in the ZIP archive
+        for zip_path in self.expected_entries.keys() {
+            if !found_entries.contains(zip_path) {
+                self.results.insert(zip_path.clone(), EntryStatus::Missing);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compares the size and the SHA-256 hash of a decompressed entry with
+    /// the source file on disk. For symbolic links the stored target path is
+    /// compared with the target of the link on disk. A source which cannot
+    /// be read is reported as unreadable, so the other entries are still
+    /// verified.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected` - The expected entry with the path of the source.
+    /// * `size` - The size of the decompressed entry.
+    /// * `hash` - The SHA-256 hash of the decompressed entry.
+    ///
+    /// # Returns
+    ///
+    /// * `EntryStatus` - The status of the entry.
+    fn compare_with_source(expected: &ArchiveEntry, size: u64, hash: Vec<u8>)
+    -> EntryStatus {
+        let source = &expected.source;
+        if let EntryKind::Symlink(target) = &expected.kind {
+            let target = target.to_string_lossy();
+            if Sha256::digest(target.as_bytes()).to_vec() == hash {
+                return EntryStatus::Intact;
+            } else {
+                return EntryStatus::Differing;
+            }
+        }
+
+        let source_size = match fs::metadata(source) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => return EntryStatus::Unreadable(e.to_string())
+        };
+        if size < source_size {
+            return EntryStatus::Truncated;
+        } else if size != source_size {
+            return EntryStatus::Differing;
+        }
+
+        let mut hasher = Sha256::new();
+        let hashed = File::open(source)
+            .and_then(|mut file| io::copy(&mut file, &mut hasher));
+        if let Err(e) = hashed {
+            return EntryStatus::Unreadable(e.to_string());
+        }
+        if hasher.finalize().to_vec() == hash {
+            EntryStatus::Intact
+        } else {
+            EntryStatus::Differing
+        }
+    }
+
+    /// Prints the verification report.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if all entries are intact, false otherwise.
+    fn display_report(&self) -> bool {
+        let mut intact = 0;
+
+        for (zip_path, status) in &self.results {
+            let message = match status {
+                EntryStatus::Intact => { intact += 1; continue; },
+                EntryStatus::Missing => "Missing in ZIP".to_string(),
+                EntryStatus::Extra => "Not part of any copy task".to_string(),
+                EntryStatus::Truncated => "Truncated".to_string(),
+                EntryStatus::Differing => "Differs from source".to_string(),
+                EntryStatus::Corrupt(e) => format!("Corrupt ({})", e),
+                EntryStatus::Unreadable(e) => {
+                    format!("Source cannot be read ({})", e)
+                }
+            };
+            println!(
+                "{}",
+                format!("!!! {}: '{}'", message, zip_path).red().bold()
+            );
+        }
+
+        let failed = self.results.len() - intact;
+
+        if failed == 0 {
+            println!(
+                "{}",
+                format!("Verification successful: {} entries intact.", intact)
+                    .green().bold()
+            );
+            true
+        } else {
+            println!(
+                "{}",
+                format!("Verification failed: {} of {} entries are not \
+                         intact.", failed, intact + failed).red().bold()
+            );
+            false
+        }
+    }
+}
+
+
+/// Writer which computes the CRC32 and the SHA-256 hash of the data that is
+/// written to it.
+struct EntryDigest {
+    crc: crc32fast::Hasher,
+    sha: Sha256
+}
+
+impl EntryDigest {
+    fn new() -> EntryDigest {
+        EntryDigest { crc: crc32fast::Hasher::new(), sha: Sha256::new() }
+    }
+
+    fn crc32(&self) -> u32 {
+        self.crc.clone().finalize()
+    }
+
+    fn sha256(self) -> Vec<u8> {
+        self.sha.finalize().to_vec()
+    }
+}
+
+impl Write for EntryDigest {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.crc.update(buf);
+        self.sha.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+
+/// Enumeration for the verification result of a ZIP entry.
+///
+/// "Intact" entries match their source file.
+/// "Missing" entries are expected but not in the ZIP archive.
+/// "Extra" entries are in the ZIP archive but not part of any copy task.
+/// "Truncated" entries are shorter than their source file.
+/// "Differing" entries have a different size or content than their source.
+/// "Corrupt" entries cannot be decompressed or fail the CRC32 check.
+/// "Unreadable" entries cannot be compared, as their source cannot be read.
+#[derive(Debug)]
+enum EntryStatus {
+    Intact,
+    Missing,
+    Extra,
+    Truncated,
+    Differing,
+    Corrupt(String),
+    Unreadable(String),
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zip::write::SimpleFileOptions;
+
+    /// Creates the config of an archive in a directory with a task for
+    /// every source.
+    fn config(root: &Path, sources: &[&str]) -> Config {
+        let tasks: Vec<_> = sources.iter().map(|source| {
+            serde_json::json!({
+                "source": root.join(source),
+                "description": source,
+                "target": ""
+            })
+        }).collect();
+
+        serde_json::from_value(serde_json::json!({
+            "zip_path": root.join("archive.zip"),
+            "direction": "verify",
+            "confirm_new": "no",
+            "confirm_overwrite": "no",
+            "confirm_delete": "no",
+            "copy_tasks": tasks
+        })).unwrap()
+    }
+
+    /// Writes an archive with uncompressed entries.
+    fn write_archive(root: &Path, entries: &[(&str, &str)]) {
+        let file = File::create(root.join("archive.zip")).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn damaged_entries_are_reported() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for name in ["a", "b", "c", "d"] {
+            fs::write(root.join(format!("{}.txt", name)), name.repeat(8))
+                .unwrap();
+        }
+        write_archive(root, &[
+            ("a.txt", "aaaaaaaa"),
+            ("b.txt", "bbbbbbbb"),
+            ("c.txt", "cccccccc"),
+            ("extra.txt", "extra")
+        ]);
+
+        // Damage the stored content of "b.txt" and let "c.txt" grow
+        let path = root.join("archive.zip");
+        let mut archive = fs::read(&path).unwrap();
+        let offset = archive.windows(8).position(|w| w == b"bbbbbbbb")
+            .unwrap();
+        archive[offset] = b'x';
+        fs::write(&path, archive).unwrap();
+        fs::write(root.join("c.txt"), "c".repeat(12)).unwrap();
+
+        let sources = ["a.txt", "b.txt", "c.txt", "d.txt"];
+        let mut verifier = ZipVerifyController::new(config(root, &sources));
+        assert!(!verifier.start());
+
+        let results = &verifier.results;
+        assert!(matches!(results["a.txt"], EntryStatus::Intact));
+        assert!(matches!(results["b.txt"], EntryStatus::Corrupt(_)));
+        assert!(matches!(results["c.txt"], EntryStatus::Truncated));
+        assert!(matches!(results["d.txt"], EntryStatus::Missing));
+        assert!(matches!(results["extra.txt"], EntryStatus::Extra));
+        assert_eq!(results.len(), 5);
+    }
+
+    #[test]
+    fn entries_of_skipped_tasks_are_ignored() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        write_archive(root, &[("a.txt", "a"), ("b.txt", "old")]);
+
+        let mut config = config(root, &["a.txt", "b.txt"]);
+        config.select_tasks(&["a.txt".to_string()], &[], &[]);
+        let mut verifier = ZipVerifyController::new(config);
+        assert!(verifier.start());
+        assert!(!verifier.results.contains_key("b.txt"));
+    }
+}
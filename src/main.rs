@@ -1,9 +1,14 @@
+mod backend;
 mod controller;
 mod model;
+use std::process::ExitCode;
 use crate::controller::main_controller::MainController;
 
 
-fn main() {
-    // Start main controller
-    MainController::new().start();
+fn main() -> ExitCode {
+    // Start main controller (a failed run results in a non-zero exit code)
+    match MainController::new().start() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE
+    }
 }
@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+
+/// A single entry of the archive together with its source on disk.
+///
+/// # Fields
+///
+/// * `zip_path` - The path of the entry in the archive (without a trailing
+///   slash for directories).
+/// * `source` - The path of the entry on disk.
+/// * `kind` - The kind of the entry.
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub zip_path: String,
+    pub source: PathBuf,
+    pub kind: EntryKind
+}
+
+/// The kind of an archive entry.
+///
+/// "File" entries contain the content of a regular file.
+/// "Directory" entries are stored with a trailing slash.
+/// "Symlink" entries contain the target path of a symbolic link.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink(PathBuf),
+}
@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Error, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use chrono::Local;
+use serde::{Serialize, Deserialize};
+
+use crate::model::file_metadata::FileMetadata;
+
+
+/// The directory the backups are stored in if the config doesn't set
+/// `backup_dir`.
+pub const DEFAULT_BACKUP_DIR: &str = "data/backups";
+
+/// The file in a backup directory which lists the backed up paths.
+const JOURNAL_FILE: &str = "backup.jsonl";
+
+
+/// A local path which was replaced or created by a `from_zip` run.
+///
+/// # Fields
+///
+/// * `path` - The local path.
+/// * `backup` - The path of the old version, relative to the backup directory
+///   (`None` if the path didn't exist before the run).
+/// * `written` - The state of the path as the run wrote it (`None` if the
+///   run didn't finish writing it).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupEntry {
+    pub path: PathBuf,
+    pub backup: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub written: Option<WrittenState>
+}
+
+/// The size and modification time of a path, which tell whether it was
+/// changed after a run wrote it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WrittenState {
+    pub size: u64,
+    pub modified: Option<SystemTime>
+}
+
+impl WrittenState {
+    /// Reads the state of a path (without following symlinks).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The local path.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(WrittenState)` - The state of the path.
+    /// * `None` - If the path doesn't exist.
+    fn of(path: &Path) -> Option<WrittenState> {
+        fs::symlink_metadata(path).ok().map(|metadata| WrittenState {
+            size: metadata.len(),
+            modified: metadata.modified().ok()
+        })
+    }
+}
+
+/// The backup of a `from_zip` run. Before a local file or symlink is
+/// replaced, its old version is moved into a timestamped directory and
+/// recorded in the journal of the backup, so the run can be undone.
+///
+/// The backup directory is only created when the first path is recorded.
+/// Every entry is appended to the journal immediately, so an interrupted run
+/// can be undone as well. Once a path is written, the entry is appended again
+/// with the state of the written path (the last line of a path counts).
+///
+/// # Fields
+///
+/// * `directory` - The directory of this backup.
+/// * `entries` - The recorded paths (in the order they were recorded).
+/// * `index` - The position of every recorded path in `entries`.
+#[derive(Debug)]
+pub struct Backup {
+    pub directory: PathBuf,
+    pub entries: Vec<BackupEntry>,
+    index: HashMap<PathBuf, usize>
+}
+
+impl Backup {
+    /// Creates a new (empty) backup in the backup root.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory containing all backups.
+    ///
+    /// # Returns
+    ///
+    /// * `Backup` - The backup, named after the current time.
+    pub fn new(root: &Path) -> Backup {
+        let name = Local::now().format("%Y-%m-%dT%H-%M-%S-%3f").to_string();
+        Backup {
+            directory: root.join(name),
+            entries: Vec::new(),
+            index: HashMap::new()
+        }
+    }
+
+    /// Returns the directories of all backups in the backup root (oldest
+    /// first).
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory containing all backups.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<PathBuf>)` - The directories of the backups.
+    /// * `Err(Error)` - If the backup root cannot be read.
+    fn list(root: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut directories = Vec::new();
+        if !root.is_dir() {
+            return Ok(directories);
+        }
+
+        for entry in fs::read_dir(root)? {
+            let path = entry?.path();
+            if path.join(JOURNAL_FILE).is_file() {
+                directories.push(path);
+            }
+        }
+
+        // The names are timestamps, so they sort from the oldest backup
+        directories.sort();
+        Ok(directories)
+    }
+
+    /// Returns the most recent backup in the backup root.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory containing all backups.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Backup))` - The most recent backup with its entries.
+    /// * `Ok(None)` - If there is no backup.
+    /// * `Err(Error)` - If the backups cannot be read.
+    pub fn latest(root: &Path) -> Result<Option<Backup>, Error> {
+        match Self::list(root)?.last() {
+            Some(directory) => Self::open(directory).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    /// Deletes the oldest backups, so only the newest ones are kept. The
+    /// newest backup is always kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory containing all backups.
+    /// * `keep` - The number of backups which are kept.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - The number of deleted backups.
+    /// * `Err(Error)` - If a backup cannot be deleted.
+    pub fn prune(root: &Path, keep: usize) -> Result<usize, Error> {
+        let directories = Self::list(root)?;
+        let deleted = directories.len().saturating_sub(keep.max(1));
+        for directory in &directories[..deleted] {
+            fs::remove_dir_all(directory)?;
+        }
+        Ok(deleted)
+    }
+
+    /// Opens an existing backup (e.g. to continue an interrupted run). A
+    /// backup without any recorded path is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory of the backup.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Backup)` - The backup with its entries.
+    /// * `Err(Error)` - If the journal of the backup cannot be read.
+    pub fn open(directory: &Path) -> Result<Backup, Error> {
+        let mut backup = Backup {
+            directory: directory.to_path_buf(),
+            entries: Vec::new(),
+            index: HashMap::new()
+        };
+        let path = directory.join(JOURNAL_FILE);
+        if path.is_file() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                // The last line may be incomplete if the process was killed
+                if let Ok(entry) = serde_json::from_str(&line?) {
+                    backup.insert(entry);
+                }
+            }
+        }
+        Ok(backup)
+    }
+
+    /// Adds an entry, or replaces the entry of the same path.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The recorded path.
+    fn insert(&mut self, entry: BackupEntry) {
+        match self.index.get(&entry.path) {
+            Some(&position) => self.entries[position] = entry,
+            None => {
+                self.index.insert(entry.path.clone(), self.entries.len());
+                self.entries.push(entry);
+            }
+        }
+    }
+
+    /// Saves the current version of a local path before it is replaced. The
+    /// file or symlink is moved into the backup; a path which doesn't exist
+    /// yet is recorded as created. Paths which were already recorded in this
+    /// run are ignored, as are files with the same content as their
+    /// replacement.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The local path which is about to be written.
+    /// * `replacement` - The file the path is replaced with (`None` for
+    ///   symlinks).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the path was saved.
+    /// * `Err(Error)` - If the old version cannot be moved or recorded.
+    pub fn save(&mut self, path: &Path, replacement: Option<&Path>)
+    -> Result<(), Error> {
+        let path = std::path::absolute(path)?;
+        if self.index.contains_key(&path) {
+            return Ok(());
+        }
+        if let Some(replacement) = replacement
+        && !path.is_symlink() && Self::same_content(&path, replacement) {
+            return Ok(());
+        }
+
+        let backup = match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => return Ok(()),
+            Ok(_metadata) => Some(Self::relative(&path)),
+            Err(_e) => None
+        };
+
+        // Recorded first, so an interrupted move is noticed when restoring
+        let entry = BackupEntry { path, backup, written: None };
+        self.append_to_journal(&entry)?;
+        if let Some(backup) = &entry.backup {
+            Self::move_path(&entry.path, &self.directory.join(backup))?;
+        }
+        self.insert(entry);
+        Ok(())
+    }
+
+    /// Checks if two files have the same content.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The path of the first file.
+    /// * `b` - The path of the second file.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if both files can be read and are equal.
+    fn same_content(a: &Path, b: &Path) -> bool {
+        let (Ok(first), Ok(second)) = (fs::metadata(a), fs::metadata(b))
+        else {
+            return false;
+        };
+        if !first.is_file() || !second.is_file()
+        || first.len() != second.len() {
+            return false;
+        }
+
+        let (Ok(mut first), Ok(mut second)) = (File::open(a), File::open(b))
+        else {
+            return false;
+        };
+        let (mut first_buffer, mut second_buffer) =
+            (vec![0; 64 * 1024], vec![0; 64 * 1024]);
+        loop {
+            let read = match first.read(&mut first_buffer) {
+                Ok(0) => return true,
+                Ok(read) => read,
+                Err(_e) => return false
+            };
+            if second.read_exact(&mut second_buffer[..read]).is_err()
+            || first_buffer[..read] != second_buffer[..read] {
+                return false;
+            }
+        }
+    }
+
+    /// Records the state of a path after the run wrote it, so changes made
+    /// after the run are noticed by `restore`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The local path which was written (and saved before).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the state was recorded (or the path wasn't saved).
+    /// * `Err(Error)` - If the journal cannot be written.
+    pub fn mark_written(&mut self, path: &Path) -> Result<(), Error> {
+        let path = std::path::absolute(path)?;
+        let Some(&position) = self.index.get(&path) else {
+            return Ok(());
+        };
+
+        let entry = &mut self.entries[position];
+        entry.written = WrittenState::of(&path);
+        let entry = entry.clone();
+        self.append_to_journal(&entry)
+    }
+
+    /// Restores the old versions of all recorded paths and removes the paths
+    /// which were created by the run. The backup is deleted afterwards.
+    /// Nothing is restored if a path was changed after the run wrote it,
+    /// unless `force` is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `force` - Whether changes made after the run are overwritten.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - The number of restored or removed paths.
+    /// * `Err(Error)` - If a path was changed after the run or cannot be
+    ///   restored (the backup is kept).
+    pub fn restore(&self, force: bool) -> Result<usize, Error> {
+        let changed: Vec<String> = self.entries.iter()
+            .filter(|entry| entry.written.is_some())
+            .filter(|entry| WrittenState::of(&entry.path)
+                .is_some_and(|state| Some(state) != entry.written))
+            .map(|entry| entry.path.display().to_string())
+            .collect();
+        if !force && !changed.is_empty() {
+            return Err(Error::other(format!(
+                "Changed after the run (zipsync undo --force restores them \
+                 anyway): {}", changed.join(", ")
+            )));
+        }
+
+        for entry in self.entries.iter().rev() {
+            // The old version wasn't moved yet (the run was interrupted)
+            let backup = entry.backup.as_ref()
+                .map(|backup| self.directory.join(backup));
+            if let Some(backup) = &backup
+            && fs::symlink_metadata(backup).is_err() {
+                continue;
+            }
+
+            if let Ok(metadata) = fs::symlink_metadata(&entry.path)
+            && !metadata.is_dir() {
+                fs::remove_file(&entry.path)?;
+            }
+            if let Some(backup) = &backup {
+                Self::move_path(backup, &entry.path)?;
+            }
+        }
+
+        if self.directory.exists() {
+            fs::remove_dir_all(&self.directory)?;
+        }
+        Ok(self.entries.len())
+    }
+
+    /// Appends an entry to the journal (and creates the backup directory if
+    /// it doesn't exist yet).
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The recorded path.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the entry was written.
+    /// * `Err(Error)` - If the journal cannot be written.
+    fn append_to_journal(&self, entry: &BackupEntry) -> Result<(), Error> {
+        fs::create_dir_all(&self.directory)?;
+        let mut journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.directory.join(JOURNAL_FILE))?;
+        writeln!(journal, "{}", serde_json::to_string(entry)?)?;
+        journal.sync_all()
+    }
+
+    /// Moves a file or symlink. If it cannot be renamed (e.g. to another
+    /// file system), it is copied and the original is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The current path.
+    /// * `to` - The new path.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the path was moved.
+    /// * `Err(Error)` - If the path can neither be renamed nor copied.
+    fn move_path(from: &Path, to: &Path) -> Result<(), Error> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::rename(from, to).is_ok() {
+            return Ok(());
+        }
+
+        if from.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(from)?, to)?;
+        } else {
+            fs::copy(from, to)?;
+            FileMetadata::from_path(from)?.apply(to)?;
+        }
+        fs::remove_file(from)
+    }
+
+    /// Returns the path of a backed up file inside the backup directory: its
+    /// absolute path without the root (and drive prefix).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The absolute local path.
+    ///
+    /// # Returns
+    ///
+    /// * `PathBuf` - The relative path inside the backup directory.
+    fn relative(path: &Path) -> PathBuf {
+        path.components()
+            .filter(|component| matches!(
+                component, std::path::Component::Normal(_)
+            ))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_keeps_changes_made_after_the_run() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("a.txt");
+        fs::write(&path, "old").unwrap();
+
+        let mut backup = Backup::new(&temp.path().join("backups"));
+        backup.save(&path, None).unwrap();
+        fs::write(&path, "extracted").unwrap();
+        backup.mark_written(&path).unwrap();
+
+        // Edited after the run
+        fs::write(&path, "edited after the run").unwrap();
+        let backup = Backup::open(&backup.directory).unwrap();
+        assert_eq!(backup.entries.len(), 1);
+        assert!(backup.restore(false).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "edited after the run");
+
+        assert_eq!(backup.restore(true).unwrap(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+    }
+
+    #[test]
+    fn save_skips_identical_files_and_prune_keeps_the_newest_backups() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("backups");
+        let (same, changed) = (temp.path().join("same"), temp.path().join("c"));
+        let replacement = temp.path().join("replacement");
+        fs::write(&same, "new").unwrap();
+        fs::write(&changed, "old").unwrap();
+        fs::write(&replacement, "new").unwrap();
+
+        let mut backup = Backup::new(&root);
+        backup.save(&same, Some(&replacement)).unwrap();
+        backup.save(&changed, Some(&replacement)).unwrap();
+        backup.save(&changed, Some(&replacement)).unwrap();
+        assert_eq!(backup.entries.len(), 1);
+        assert_eq!(backup.entries[0].path, changed);
+
+        for name in ["2026-01-01T00-00-00-000", "2026-01-02T00-00-00-000"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            fs::write(root.join(name).join(JOURNAL_FILE), "").unwrap();
+        }
+        assert_eq!(Backup::prune(&root, 2).unwrap(), 1);
+        assert!(!root.join("2026-01-01T00-00-00-000").exists());
+        assert_eq!(Backup::latest(&root).unwrap().unwrap().directory,
+                   backup.directory);
+        assert_eq!(Backup::prune(&root, 0).unwrap(), 1);
+        assert!(backup.directory.exists());
+    }
+}
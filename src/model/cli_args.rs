@@ -0,0 +1,124 @@
+use std::io::{Error, ErrorKind};
+
+
+/// The fields of a copy task which can be set on the command line
+/// (e.g. `--source`).
+const TASK_FIELDS: &[&str] = &[
+    "name", "source", "target", "description", "symlinks", "compression",
+    "tags", "required", "enabled", "only_on_hosts", "only_on_os",
+];
+
+
+/// The command line arguments of the application.
+///
+/// # Fields
+///
+/// * `command` - The positional arguments (e.g. `["config", "convert", ...]`).
+///   Without a command, the configured zip operation is run.
+/// * `config_path` - The path of the config file given with `--config`.
+/// * `profiles` - The profiles which are selected with `--profile`.
+/// * `all_profiles` - Whether all profiles are selected with `--all`.
+/// * `task_fields` - The copy task fields given for `task add` and
+///   `task edit` (e.g. `("source", "/home/user/docs")`).
+/// * `tasks` - The copy tasks which are selected with `--task` (by name or
+///   description).
+/// * `tags` - The tags of the copy tasks which are selected with `--tag`.
+/// * `exclude_tags` - The tags of the copy tasks which are excluded with
+///   `--exclude-tag`.
+/// * `path` - The path in the archive which is restored (`--path`).
+/// * `force` - Whether `undo` restores files which were changed after the
+///   run (`--force`).
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub command: Vec<String>,
+    pub config_path: Option<String>,
+    pub profiles: Vec<String>,
+    pub all_profiles: bool,
+    pub task_fields: Vec<(String, String)>,
+    pub tasks: Vec<String>,
+    pub tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    pub path: Option<String>,
+    pub force: bool
+}
+
+impl CliArgs {
+    /// Parses the command line arguments (without the program name).
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The command line arguments.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CliArgs)` - The parsed arguments.
+    /// * `Err(Error)` - If an argument is unknown or a value is missing.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I)
+    -> Result<Self, Error> {
+        let mut cli_args = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--profile" | "-p" => {
+                    cli_args.profiles.push(Self::value(&arg, args.next())?);
+                },
+                "--all" => cli_args.all_profiles = true,
+                "--force" => cli_args.force = true,
+                "--config" | "-c" => {
+                    let path = Self::value(&arg, args.next())?;
+                    cli_args.config_path = Some(path);
+                },
+                "--task" | "-t" => {
+                    cli_args.tasks.push(Self::value(&arg, args.next())?);
+                },
+                "--tag" => {
+                    cli_args.tags.push(Self::value(&arg, args.next())?);
+                },
+                "--exclude-tag" => {
+                    let tag = Self::value(&arg, args.next())?;
+                    cli_args.exclude_tags.push(tag);
+                },
+                "--path" => {
+                    cli_args.path = Some(Self::value(&arg, args.next())?);
+                },
+                other if other.strip_prefix("--")
+                    .is_some_and(|field| TASK_FIELDS.contains(&field)) => {
+                    let value = Self::value(other, args.next())?;
+                    cli_args.task_fields.push((other[2..].to_string(), value));
+                },
+                other if !other.starts_with('-') => {
+                    cli_args.command.push(other.to_string());
+                },
+                other => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unknown argument: {}", other)
+                    ));
+                }
+            }
+        }
+
+        Ok(cli_args)
+    }
+
+    /// Returns the value of an option or an error if it is missing.
+    ///
+    /// # Arguments
+    ///
+    /// * `option` - The name of the option.
+    /// * `value` - The next command line argument.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The value of the option.
+    /// * `Err(Error)` - If the value is missing.
+    fn value(option: &str, value: Option<String>) -> Result<String, Error> {
+        value.filter(|value| !value.starts_with("--")).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Missing value for {}", option)
+            )
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::Path;
+use zip::CompressionMethod;
+
+use crate::model::config::Config;
+use crate::model::copy_task::CopyTask;
+
+
+/// File extensions of formats which are already compressed. Files with these
+/// extensions are always stored without compression.
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "avif", "br", "bz2", "docx", "flac", "gif", "gz", "heic", "jar",
+    "jpeg", "jpg", "lz4", "m4a", "m4v", "mkv", "mov", "mp3", "mp4", "odt",
+    "ogg", "opus", "png", "rar", "tgz", "webm", "webp", "xlsx", "xz", "zip",
+    "zst",
+];
+
+
+/// The compression settings for the files of a copy task.
+///
+/// # Fields
+///
+/// * `method` - The compression method.
+/// * `level` - The compression level (`None` for the default level).
+#[derive(Debug, Clone, Copy)]
+pub struct Compression {
+    pub method: CompressionMethod,
+    pub level: Option<i64>
+}
+
+impl Compression {
+    /// Determines the compression settings of a copy task. The settings of
+    /// the task take precedence over the settings of the config.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config with the default compression settings.
+    /// * `task` - The copy task.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Compression)` - The compression settings.
+    /// * `None` - If the compression method is unknown.
+    pub fn from_settings(config: &Config, task: &CopyTask) -> Option<Self> {
+        let name = if task.compression.is_empty() {
+            &config.compression
+        } else {
+            &task.compression
+        };
+
+        let method = match name.as_str() {
+            "" | "deflate" => CompressionMethod::Deflated,
+            "stored" => CompressionMethod::Stored,
+            "bzip2" => CompressionMethod::Bzip2,
+            "zstd" => CompressionMethod::Zstd,
+            "xz" => CompressionMethod::Xz,
+            _ => return None
+        };
+
+        // The level of the config only applies to the method of the config;
+        // stored files don't have a compression level
+        let level = match method {
+            CompressionMethod::Stored => None,
+            _ if !task.compression.is_empty() => task.compression_level,
+            _ => task.compression_level.or(config.compression_level)
+        };
+
+        Some(Self { method, level })
+    }
+
+    /// Returns the compression settings for a single file. Files which are
+    /// already compressed (e.g. "jpg", "mp4", "zip") are stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Compression` - The compression settings for the file.
+    pub fn for_file(&self, path: &str) -> Self {
+        let extension = Path::new(path).extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if COMPRESSED_EXTENSIONS.contains(&extension.as_str()) {
+            Self { method: CompressionMethod::Stored, level: None }
+        } else {
+            *self
+        }
+    }
+}
@@ -1,50 +1,85 @@
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
+use crate::model::backup::DEFAULT_BACKUP_DIR;
 use crate::model::copy_task::CopyTask;
+use crate::model::password_source::PasswordSource;
+use crate::model::retention::Retention;
+use crate::model::snapshot::Snapshot;
 
 
 /// The configuration object which holds the settings for the application
 /// and the copy tasks.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub zip_path: String,
+    #[serde(default)]
+    pub format: String,
     pub direction: String,
     pub confirm_new: String,
     pub confirm_overwrite: String,
     pub confirm_delete: String,
-    pub copy_tasks: Vec<CopyTask>
+    #[serde(default)]
+    pub manifest: String,
+    #[serde(default)]
+    pub manifest_hash: String,
+    #[serde(default)]
+    pub compression: String,
+    #[serde(default)]
+    pub compression_level: Option<i64>,
+    #[serde(default)]
+    pub threads: Option<usize>,
+    #[serde(default)]
+    pub watch_debounce_ms: Option<u64>,
+    #[serde(default)]
+    pub retention: Option<Retention>,
+    #[serde(default)]
+    pub encryption: String,
+    #[serde(default)]
+    pub password_source: Option<PasswordSource>,
+    #[serde(default)]
+    pub on_error: String,
+    #[serde(default)]
+    pub backup: String,
+    #[serde(default)]
+    pub backup_dir: String,
+    #[serde(default)]
+    pub backup_keep: Option<usize>,
+    #[serde(default)]
+    pub pre_hooks: Vec<String>,
+    #[serde(default)]
+    pub post_hooks: Vec<String>,
+    pub copy_tasks: Vec<CopyTask>,
+    #[serde(skip)]
+    pub profile: Option<String>,
+    #[serde(skip)]
+    pub snapshot_template: Option<String>,
+    #[serde(skip)]
+    pub skipped_tasks: Vec<CopyTask>
 }
 
 /// Implementation of the `Config` struct.
 ///
 /// # Methods
 ///
-/// * `new` - Creates a new instance of the `Config` struct.
-/// * `clean` - Removes leading slashes from the target paths of the copy tasks.
+/// * `clean` - Removes leading slashes from the target paths of the copy tasks
+///   and detects snapshot templates.
+/// * `use_snapshot` - Selects a snapshot as the archive.
+/// * `backup_root` - Returns the directory of the backups.
+/// * `select_tasks` - Keeps only the copy tasks selected on the command line.
+/// * `skip_inactive_tasks` - Skips the copy tasks which don't run on this
+///   machine.
+/// * `error_policy` - Returns what happens if a required task fails.
 ///
 /// # Examples
 ///
 /// ```
-/// let mut config = Config::new();
+/// let mut config: Config = serde_json::from_reader(reader)?;
 /// config.clean();
 /// ```
 impl Config {
-    /// Creates a new instance of the `Config` struct.
-    ///
-    /// # Returns
-    ///
-    /// * `Config` - A new instance of the `Config` struct.
-    pub fn new() -> Self {
-        Self {
-            zip_path: String::new(),
-            direction: String::new(),
-            confirm_new: String::new(),
-            confirm_overwrite: String::new(),
-            confirm_delete: String::new(),
-            copy_tasks: Vec::new(),
-        }
-    }
-
     /// Removes leading slashes from the target paths of the copy tasks.
+    /// A `zip_path` with format specifiers is kept as snapshot template.
     pub fn clean(&mut self) {
         // Remove leading slashes from the target paths of the copy tasks
         for task in self.copy_tasks.iter_mut() {
@@ -52,5 +87,148 @@ impl Config {
                 task.target = task.target[1..].to_string();
             }
         }
+
+        if Snapshot::is_template(&self.zip_path) {
+            self.snapshot_template = Some(self.zip_path.clone());
+        }
     }
-}
\ No newline at end of file
+
+    /// Uses a snapshot as the archive if the `zip_path` is a snapshot
+    /// template.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The number of the snapshot (1 = newest) or the
+    ///   beginning of its timestamp (e.g. "2026-10-16" or "2026-10-16T12");
+    ///   the newest matching snapshot is used. `None` selects the newest
+    ///   snapshot.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the archive path was set (or no template is used).
+    /// * `Err(Error)` - If no snapshot matches or a snapshot is selected
+    ///   without a template.
+    pub fn use_snapshot(&mut self, selector: Option<&str>)
+    -> Result<(), Error> {
+        let Some(template) = &self.snapshot_template else {
+            return match selector {
+                None => Ok(()),
+                Some(_) => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'{}' is not a snapshot template", self.zip_path)
+                ))
+            };
+        };
+
+        let snapshots = Snapshot::list(template)?;
+        let snapshot = match selector {
+            None => snapshots.first(),
+            Some(selector) => match selector.parse::<usize>() {
+                Ok(number) => number.checked_sub(1)
+                    .and_then(|index| snapshots.get(index)),
+                Err(_) => snapshots.iter()
+                    .find(|snapshot| snapshot.matches(selector))
+            }
+        }.ok_or_else(|| Error::new(
+            ErrorKind::NotFound,
+            format!("No snapshot found for '{}'", template)
+        ))?;
+
+        self.zip_path = snapshot.path.to_string_lossy().to_string();
+        Ok(())
+    }
+
+    /// Returns the directory of the backups which are created before
+    /// `from_zip` replaces local files. Every profile has its own directory.
+    ///
+    /// # Returns
+    ///
+    /// * `PathBuf` - The backup directory of this config.
+    pub fn backup_root(&self) -> PathBuf {
+        let root = if self.backup_dir.is_empty() {
+            PathBuf::from(DEFAULT_BACKUP_DIR)
+        } else {
+            PathBuf::from(&self.backup_dir)
+        };
+
+        match &self.profile {
+            Some(profile) => root.join(profile),
+            None => root
+        }
+    }
+
+    /// Keeps only the copy tasks which are selected by name or tag; the other
+    /// tasks are moved to `skipped_tasks`. Without names and tags all tasks
+    /// are selected. Tasks with an excluded tag are never selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names (or descriptions) of the selected tasks.
+    /// * `tags` - The tags of the selected tasks.
+    /// * `exclude_tags` - The tags of the excluded tasks.
+    pub fn select_tasks(
+        &mut self, names: &[String], tags: &[String], exclude_tags: &[String]
+    ) {
+        let (selected, skipped): (Vec<CopyTask>, Vec<CopyTask>) = self
+            .copy_tasks.drain(..)
+            .partition(|task| {
+                let included = (names.is_empty() && tags.is_empty())
+                    || names.iter().any(|name| task.is_named(name))
+                    || tags.iter().any(|tag| task.has_tag(tag));
+                included && !exclude_tags.iter().any(|tag| task.has_tag(tag))
+            });
+        self.copy_tasks = selected;
+        self.skipped_tasks.extend(skipped);
+    }
+
+    /// Moves the copy tasks which are disabled or don't run on this machine
+    /// (`only_on_hosts`, `only_on_os`) to `skipped_tasks`.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The name of this machine (if known).
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(CopyTask, String)>` - The skipped tasks and the reasons.
+    pub fn skip_inactive_tasks(&mut self, machine: Option<&str>)
+    -> Vec<(CopyTask, String)> {
+        let mut inactive = Vec::new();
+        self.copy_tasks.retain(|task| {
+            match task.inactive_reason(machine, std::env::consts::OS) {
+                Some(reason) => {
+                    inactive.push((task.clone(), reason));
+                    false
+                },
+                None => true
+            }
+        });
+
+        self.skipped_tasks.extend(
+            inactive.iter().map(|(task, _reason)| task.clone())
+        );
+        inactive
+    }
+
+    /// Returns the policy for failures of required copy tasks (`on_error`):
+    /// "continue" (the default; the run fails at the end), "abort" (no
+    /// further tasks are processed) or "abort_without_writing" (all required
+    /// sources are checked before the archive or a local file is written).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&str)` - The policy.
+    /// * `Err(Error)` - If the policy is unknown.
+    pub fn error_policy(&self) -> Result<&str, Error> {
+        match self.on_error.as_str() {
+            "" => Ok("continue"),
+            policy @ ("continue" | "abort" | "abort_without_writing") => {
+                Ok(policy)
+            },
+            other => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown on_error policy: {}", other)
+            ))
+        }
+    }
+}
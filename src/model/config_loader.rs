@@ -0,0 +1,556 @@
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use serde_json::{Map, Value};
+
+use crate::model::config::Config;
+
+
+/// The environment variable which overrides the hostname for selecting the
+/// machine override file.
+pub const MACHINE_ENV: &str = "ZIPSYNC_MACHINE";
+
+/// The paths which are searched for the config file (the first existing one
+/// is used).
+const DEFAULT_CONFIG_PATHS: &[&str] = &[
+    "data/config.json", "data/config.toml", "data/config.yaml",
+    "data/config.yml",
+];
+
+
+/// The file formats of config files, detected by the extension.
+///
+/// "Json" files don't allow comments.
+/// "Toml" and "Yaml" files allow comments, but have no `null` values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Determines the format of a config file from its extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ConfigFormat)` - The format of the file.
+    /// * `Err(Error)` - If the extension is unknown.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let extension = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown config format: {}", path.display())
+            ))
+        }
+    }
+
+    /// Parses the content of a config file.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value)` - The parsed content.
+    /// * `Err(Error)` - If the content is invalid.
+    pub fn parse(&self, content: &str) -> Result<Value, Error> {
+        let invalid = |e: String| Error::new(ErrorKind::InvalidData, e);
+        match self {
+            Self::Json => serde_json::from_str(content)
+                .map_err(|e| invalid(e.to_string())),
+            Self::Toml => toml::from_str(content)
+                .map_err(|e| invalid(e.to_string())),
+            Self::Yaml => serde_yaml::from_str(content)
+                .map_err(|e| invalid(e.to_string()))
+        }
+    }
+
+    /// Serializes a config object. `null` values are omitted for TOML.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The config object.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The content of the config file.
+    /// * `Err(Error)` - If the value cannot be serialized.
+    pub fn serialize(&self, value: &Value) -> Result<String, Error> {
+        match self {
+            Self::Json => Ok(serde_json::to_string_pretty(value)? + "\n"),
+            Self::Toml => toml::to_string_pretty(&Self::without_nulls(value))
+                .map_err(Error::other),
+            Self::Yaml => serde_yaml::to_string(value).map_err(Error::other)
+        }
+    }
+
+    /// Returns a copy of a value without `null` values (in objects and
+    /// arrays).
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value.
+    ///
+    /// # Returns
+    ///
+    /// * `Value` - The value without `null` values.
+    fn without_nulls(value: &Value) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(object.iter()
+                .filter(|(_key, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), Self::without_nulls(value)))
+                .collect()),
+            Value::Array(array) => Value::Array(array.iter()
+                .filter(|value| !value.is_null())
+                .map(Self::without_nulls)
+                .collect()),
+            other => other.clone()
+        }
+    }
+}
+
+
+/// Reads a config file and resolves it into the configs which are run.
+///
+/// A config file can `include` other config files. The included files are
+/// merged first (in their order), then the fields of the including file are
+/// applied: `copy_tasks` are appended, `profiles` are merged by name and all
+/// other fields are replaced.
+///
+/// A config file can define named profiles in a top-level `profiles` map.
+/// Every profile is a partial config; its fields replace the fields of the
+/// top level, which serves as the shared defaults of all profiles.
+///
+/// Finally a machine override file next to the config file
+/// (`<name>.<machine>.<extension>`, e.g. "config.laptop.json") is applied to
+/// every profile. Besides replacing fields and appending `copy_tasks`, it can
+/// change the `source_roots` of the tasks and `disable_tasks` by name.
+pub struct ConfigLoader {
+    /// The path of the config file.
+    path: PathBuf
+}
+
+impl ConfigLoader {
+    /// Creates a new `ConfigLoader` for a config file. Without a path, the
+    /// first existing file of "data/config.json", "data/config.toml" and
+    /// "data/config.yaml" is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the config file (if given on the command line).
+    ///
+    /// # Returns
+    ///
+    /// * `ConfigLoader` - A new instance of the `ConfigLoader` struct.
+    pub fn new(path: Option<&str>) -> Self {
+        let path = path.unwrap_or_else(|| {
+            DEFAULT_CONFIG_PATHS.iter()
+                .find(|path| Path::new(path).is_file())
+                .unwrap_or(&DEFAULT_CONFIG_PATHS[0])
+        });
+        Self { path: PathBuf::from(path) }
+    }
+
+    /// Returns the path of the config file.
+    ///
+    /// # Returns
+    ///
+    /// * `&Path` - The path of the config file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the config file and returns the configs of the selected
+    /// profiles. A config file without profiles results in a single config.
+    ///
+    /// # Arguments
+    ///
+    /// * `profiles` - The names of the selected profiles.
+    /// * `all_profiles` - Whether all profiles are selected.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Config>)` - The configs in the order of the selection.
+    /// * `Err(Error)` - If the file cannot be read or parsed, or if the
+    ///   selection doesn't match the profiles of the file.
+    pub fn load(&self, profiles: &[String], all_profiles: bool)
+    -> Result<Vec<Config>, Error> {
+        let mut defaults = Self::read_layers(&self.path, &mut Vec::new())?;
+        let machine_override = match self.machine_override_path() {
+            Some(path) => Some(Self::read_layers(&path, &mut Vec::new())?),
+            None => None
+        };
+
+        let Some(available) = defaults.remove("profiles") else {
+            if !profiles.is_empty() || all_profiles {
+                return Err(Self::invalid(
+                    "The config doesn't define profiles"
+                ));
+            }
+            return Ok(vec![Self::to_config(
+                defaults, machine_override.as_ref(), None
+            )?]);
+        };
+        let Value::Object(available) = available else {
+            return Err(Self::invalid("'profiles' must be a JSON object"));
+        };
+
+        // Select the profiles
+        let names: Vec<String> = if all_profiles {
+            available.keys().cloned().collect()
+        } else if profiles.is_empty() {
+            return Err(Self::invalid(&format!(
+                "Select a profile with --profile <name> or run all profiles \
+                 with --all. Available profiles: {}",
+                available.keys().cloned().collect::<Vec<_>>().join(", ")
+            )));
+        } else {
+            profiles.to_vec()
+        };
+
+        let mut configs = Vec::new();
+        for name in names {
+            let Some(Value::Object(profile)) = available.get(&name) else {
+                return Err(Self::invalid(
+                    &format!("Unknown profile: {}", name)
+                ));
+            };
+
+            let mut merged = defaults.clone();
+            merged.extend(profile.clone());
+            configs.push(Self::to_config(
+                merged, machine_override.as_ref(), Some(name)
+            )?);
+        }
+
+        Ok(configs)
+    }
+
+    /// Returns the name of the machine which selects the override file: the
+    /// value of `ZIPSYNC_MACHINE` or, if not set, the hostname.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` - The name of the machine.
+    /// * `None` - If the hostname cannot be determined.
+    pub fn machine_name() -> Option<String> {
+        env::var(MACHINE_ENV).ok()
+            .filter(|name| !name.is_empty())
+            .or_else(|| {
+                hostname::get().ok()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+    }
+
+    /// Returns the path of the machine override file if it exists
+    /// (e.g. "data/config.laptop.json" for "data/config.json").
+    ///
+    /// # Returns
+    ///
+    /// * `Some(PathBuf)` - The path of the override file.
+    /// * `None` - If there is no override file for this machine.
+    pub fn machine_override_path(&self) -> Option<PathBuf> {
+        let machine = Self::machine_name()?;
+        let stem = self.path.file_stem()?.to_string_lossy();
+        let name = match self.path.extension() {
+            Some(ext) => {
+                format!("{}.{}.{}", stem, machine, ext.to_string_lossy())
+            },
+            None => format!("{}.{}", stem, machine)
+        };
+
+        let path = self.path.with_file_name(name);
+        path.is_file().then_some(path)
+    }
+
+    /// Reads a config file and recursively merges the files it includes.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the config file.
+    /// * `stack` - The canonical paths of the including files (used to
+    ///   detect include cycles).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Map)` - The merged JSON object.
+    /// * `Err(Error)` - If a file cannot be read or parsed, or if the
+    ///   includes form a cycle.
+    fn read_layers(path: &Path, stack: &mut Vec<PathBuf>)
+    -> Result<Map<String, Value>, Error> {
+        let canonical_path = fs::canonicalize(path).map_err(|e| {
+            Error::new(e.kind(), format!("{}: {}", path.display(), e))
+        })?;
+        if stack.contains(&canonical_path) {
+            return Err(Self::invalid(&format!(
+                "Include cycle: {}", path.display()
+            )));
+        }
+
+        let Value::Object(mut layer) = Self::read_value(path)? else {
+            return Err(Self::invalid(&format!(
+                "The config must be an object: {}", path.display()
+            )));
+        };
+
+        // Included files are relative to the including file
+        let includes = match layer.remove("include") {
+            None => Vec::new(),
+            Some(Value::String(include)) => vec![include],
+            Some(Value::Array(includes)) => includes.into_iter()
+                .filter_map(|include| include.as_str().map(String::from))
+                .collect(),
+            Some(_) => {
+                return Err(Self::invalid(&format!(
+                    "'include' must be a path or a list of paths: {}",
+                    path.display()
+                )));
+            }
+        };
+
+        stack.push(canonical_path);
+        let mut merged = Map::new();
+        for include in includes {
+            let include_path = path.parent().unwrap_or(Path::new(""))
+                .join(include);
+            let included = Self::read_layers(&include_path, stack)?;
+            Self::merge_layer(&mut merged, included);
+        }
+        stack.pop();
+
+        Self::merge_layer(&mut merged, layer);
+        Ok(merged)
+    }
+
+    /// Reads a config file (JSON, TOML or YAML) into a JSON value.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value)` - The content of the config file.
+    /// * `Err(Error)` - If the file cannot be read or parsed.
+    pub fn read_value(path: &Path) -> Result<Value, Error> {
+        let format = ConfigFormat::from_path(path)?;
+        let content = fs::read_to_string(path)?;
+        format.parse(&content).map_err(|e| {
+            Error::new(e.kind(), format!("{}: {}", path.display(), e))
+        })
+    }
+
+    /// Merges a layer into a config object: `copy_tasks` are appended,
+    /// `profiles` are merged by name and all other fields are replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The config object the layer is merged into.
+    /// * `layer` - The layer.
+    fn merge_layer(base: &mut Map<String, Value>, layer: Map<String, Value>) {
+        for (key, value) in layer {
+            match (key.as_str(), base.get_mut(&key), value) {
+                ("copy_tasks", Some(Value::Array(tasks)),
+                 Value::Array(added)) => {
+                    tasks.extend(added);
+                },
+                ("profiles", Some(Value::Object(profiles)),
+                 Value::Object(added)) => {
+                    profiles.extend(added);
+                },
+                (_, _, value) => {
+                    base.insert(key, value);
+                }
+            }
+        }
+    }
+
+    /// Applies the machine override file to a config object. The source
+    /// roots of the tasks are replaced (the longest matching root wins) and
+    /// disabled tasks are marked as not enabled, so they are skipped like
+    /// unselected tasks and their entries stay in the archive. The remaining
+    /// fields are merged afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config object of a profile.
+    /// * `machine_override` - The content of the override file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the override was applied.
+    /// * `Err(Error)` - If `source_roots` or `disable_tasks` are invalid.
+    fn apply_override(
+        config: &mut Map<String, Value>,
+        machine_override: &Map<String, Value>
+    ) -> Result<(), Error> {
+        let mut machine_override = machine_override.clone();
+
+        let roots = match machine_override.remove("source_roots") {
+            None => Map::new(),
+            Some(Value::Object(roots)) => roots,
+            Some(_) => {
+                return Err(Self::invalid("'source_roots' must be an object"));
+            }
+        };
+        let disabled: Vec<String> = match machine_override
+            .remove("disable_tasks") {
+            None => Vec::new(),
+            Some(Value::Array(names)) => names.iter()
+                .filter_map(|name| name.as_str().map(String::from))
+                .collect(),
+            Some(_) => {
+                return Err(Self::invalid("'disable_tasks' must be a list"));
+            }
+        };
+
+        if let Some(Value::Array(tasks)) = config.get_mut("copy_tasks") {
+            for task in tasks.iter_mut() {
+                if disabled.contains(&Self::task_name(task))
+                && let Value::Object(task) = task {
+                    task.insert("enabled".to_string(), Value::from("no"));
+                }
+
+                let Some(Value::String(source)) = task.get_mut("source")
+                else {
+                    continue;
+                };
+                let replaced = roots.iter()
+                    .filter_map(|(root, replacement)| {
+                        let rest = Self::strip_root(source, root)?;
+                        Some((root.trim_end_matches('/').len(),
+                              format!("{}{}", replacement.as_str()?, rest)))
+                    })
+                    .max_by_key(|(length, _source)| *length);
+                if let Some((_length, replaced)) = replaced {
+                    *source = replaced;
+                }
+            }
+        }
+
+        Self::merge_layer(config, machine_override);
+        Ok(())
+    }
+
+    /// Returns the name of a task object: its `name` or, if it has none,
+    /// its `description`.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task object.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The name of the task.
+    fn task_name(task: &Value) -> String {
+        ["name", "description"].iter()
+            .filter_map(|key| task.get(key).and_then(Value::as_str))
+            .find(|name| !name.is_empty())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Removes a root directory from the beginning of a path. The root has
+    /// to match whole path components ("/home/a" doesn't match "/home/ab").
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path.
+    /// * `root` - The root directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&str)` - The rest of the path (starting with a slash).
+    /// * `None` - If the path isn't inside of the root.
+    fn strip_root<'a>(path: &'a str, root: &str) -> Option<&'a str> {
+        let rest = path.strip_prefix(root.trim_end_matches('/'))?;
+        (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+    }
+
+    /// Converts a (merged) JSON object into a cleaned config.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The JSON object.
+    /// * `machine_override` - The content of the machine override file.
+    /// * `profile` - The name of the profile (if any).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Config)` - The config.
+    /// * `Err(Error)` - If the object isn't a valid config.
+    fn to_config(
+        mut object: Map<String, Value>,
+        machine_override: Option<&Map<String, Value>>,
+        profile: Option<String>
+    ) -> Result<Config, Error> {
+        if let Some(machine_override) = machine_override {
+            Self::apply_override(&mut object, machine_override)?;
+        }
+
+        let mut config: Config = serde_json::from_value(Value::Object(object))?;
+        config.profile = profile;
+        config.clean();
+        Ok(config)
+    }
+
+    /// Creates an error for an invalid config.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The error message.
+    ///
+    /// # Returns
+    ///
+    /// * `Error` - The error.
+    fn invalid(message: &str) -> Error {
+        Error::new(ErrorKind::InvalidData, message.to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn override_uses_the_longest_root_and_skips_disabled_tasks() {
+        let Value::Object(mut config) = json!({
+            "copy_tasks": [
+                { "name": "docs", "source": "/home/alice/docs",
+                  "description": "Docs", "target": "" },
+                { "name": "photos", "source": "/home/alice/photos/2026",
+                  "description": "Photos", "target": "" }
+            ]
+        }) else { unreachable!() };
+        let Value::Object(machine_override) = json!({
+            "source_roots": {
+                "/home/alice": "/Users/alice",
+                "/home/alice/photos/": "/Volumes/photos"
+            },
+            "disable_tasks": ["docs"]
+        }) else { unreachable!() };
+
+        ConfigLoader::apply_override(&mut config, &machine_override).unwrap();
+        let tasks = &config["copy_tasks"];
+        assert_eq!(tasks[0]["source"], "/Users/alice/docs");
+        assert_eq!(tasks[0]["enabled"], "no");
+        assert_eq!(tasks[1]["source"], "/Volumes/photos/2026");
+        assert!(tasks[1].get("enabled").is_none());
+    }
+}
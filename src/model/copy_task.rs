@@ -1,4 +1,4 @@
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 
 /// A struct representing a copy task.
@@ -7,12 +7,221 @@ use serde::{Serialize, Deserialize};
 ///
 /// # Fields
 ///
+/// * `name` - A short identifier of the task (e.g. for disabling it in a
+///   machine override).
 /// * `source` - The source directory or file.
 /// * `description` - A human-readable description of the task.
 /// * `target` - The target directory.
+/// * `symlinks` - How symbolic links are handled: "follow" (default), "store"
+///   or "skip".
+/// * `compression` - The compression method (overrides the config).
+/// * `compression_level` - The compression level (overrides the config).
+/// * `tags` - Labels for selecting a group of tasks (e.g. "work", "large").
+/// * `required` - "yes" if a failure of the task fails the run (see
+///   `on_error` of the config). `true` and `false` are accepted as well.
+/// * `enabled` - "no" disables the task (default: "yes"). `true` and `false`
+///   are accepted as well.
+/// * `only_on_hosts` - The machines the task runs on (all if empty).
+/// * `only_on_os` - The operating systems the task runs on, e.g. "linux",
+///   "macos" or "windows" (all if empty).
+/// * `pre_hooks` - Shell commands which run before the task is archived or
+///   extracted (after the hooks of the config).
+/// * `post_hooks` - Shell commands which run after the task was archived or
+///   extracted (before the hooks of the config).
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CopyTask {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
     pub source: String,
     pub description: String,
-    pub target: String
-}
\ No newline at end of file
+    pub target: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub symlinks: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub compression: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_level: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, deserialize_with = "yes_no",
+            skip_serializing_if = "String::is_empty")]
+    pub required: String,
+    #[serde(default, deserialize_with = "yes_no",
+            skip_serializing_if = "String::is_empty")]
+    pub enabled: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only_on_hosts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only_on_os: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_hooks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_hooks: Vec<String>
+}
+
+impl CopyTask {
+    /// Checks if the task is selected by a name (its `name` or its
+    /// `description`).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the selected task.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the task has this name or description.
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.description == name
+    }
+
+    /// Checks if the task has a tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the task has this tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|task_tag| task_tag == tag)
+    }
+
+    /// Checks if a failure of the task fails the run.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the task is required.
+    pub fn is_required(&self) -> bool {
+        yes_no_value(&self.required) == Some("yes")
+    }
+
+    /// Checks if the task runs on this machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `machine` - The name of this machine (if known).
+    /// * `os` - The operating system of this machine (e.g. "linux").
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` - The reason why the task doesn't run here.
+    /// * `None` - If the task runs on this machine.
+    pub fn inactive_reason(&self, machine: Option<&str>, os: &str)
+    -> Option<String> {
+        if self.enabled == "no" {
+            return Some("disabled".to_string());
+        }
+
+        let on_host = machine.is_some_and(|machine| {
+            self.only_on_hosts.iter()
+                .any(|host| host.eq_ignore_ascii_case(machine))
+        });
+        if !self.only_on_hosts.is_empty() && !on_host {
+            return Some(format!(
+                "only on host {}", self.only_on_hosts.join(", ")
+            ));
+        }
+
+        let on_os = self.only_on_os.iter()
+            .any(|task_os| task_os.eq_ignore_ascii_case(os));
+        if !self.only_on_os.is_empty() && !on_os {
+            return Some(format!("only on {}", self.only_on_os.join(", ")));
+        }
+
+        None
+    }
+}
+
+
+/// Normalises the value of a "yes"/"no" setting. "true" and "false" are
+/// accepted as well (e.g. from the command line), in any case.
+///
+/// # Arguments
+///
+/// * `value` - The value of the setting.
+///
+/// # Returns
+///
+/// * `Some(&str)` - The setting ("yes" or "no").
+/// * `None` - If the value isn't a "yes"/"no" setting.
+pub fn yes_no_value(value: &str) -> Option<&'static str> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "true" => Some("yes"),
+        "no" | "false" => Some("no"),
+        _ => None
+    }
+}
+
+/// Deserializes a "yes"/"no" setting which might also be written as a
+/// boolean (e.g. `"required": true`); booleans are stored as "yes" or "no".
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer of the setting.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The setting ("yes" or "no").
+/// * `Err(D::Error)` - If the setting is neither "yes", "no", "true",
+///   "false" nor a boolean.
+fn yes_no<'de, D: Deserializer<'de>>(deserializer: D)
+-> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum YesNo {
+        Bool(bool),
+        Text(String),
+    }
+
+    let value = match YesNo::deserialize(deserializer) {
+        Ok(YesNo::Bool(true)) => Some("yes"),
+        Ok(YesNo::Bool(false)) => Some("no"),
+        Ok(YesNo::Text(text)) => yes_no_value(&text),
+        Err(_e) => None
+    };
+    value.map(str::to_string).ok_or_else(|| serde::de::Error::custom(
+        "expected \"yes\", \"no\" or a boolean"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yes_no_settings_accept_booleans() {
+        for (value, expected) in [
+            (serde_json::json!(true), "yes"),
+            (serde_json::json!(false), "no"),
+            (serde_json::json!("yes"), "yes"),
+            (serde_json::json!("no"), "no"),
+            (serde_json::json!("False"), "no")
+        ] {
+            let task: CopyTask = serde_json::from_value(serde_json::json!({
+                "source": "a",
+                "description": "a",
+                "target": "",
+                "required": value,
+                "enabled": value
+            })).unwrap();
+            assert_eq!(task.required, expected);
+            assert_eq!(task.enabled, expected);
+        }
+
+        let task: CopyTask = toml::from_str(
+            "source = 'a'\ndescription = 'a'\ntarget = ''\nenabled = false"
+        ).unwrap();
+        assert_eq!(task.inactive_reason(None, "linux").unwrap(), "disabled");
+        let task: CopyTask = toml::from_str(
+            "source = 'a'\ndescription = 'a'\ntarget = ''\nrequired = true"
+        ).unwrap();
+        assert!(task.is_required());
+        for value in [serde_json::json!(1), serde_json::json!("maybe")] {
+            assert!(serde_json::from_value::<CopyTask>(serde_json::json!({
+                "source": "a", "description": "a", "target": "",
+                "enabled": value
+            })).is_err());
+        }
+    }
+}
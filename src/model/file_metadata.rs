@@ -0,0 +1,247 @@
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use chrono::{Datelike, Local, LocalResult, TimeZone, Timelike};
+use zip::extra_fields::ExtraField;
+use zip::read::ZipFile;
+use zip::write::FullFileOptions;
+use zip::CompressionMethod;
+
+
+/// The header ID of the extended timestamp extra field ("UT").
+const EXTENDED_TIMESTAMP_ID: u16 = 0x5455;
+
+
+/// The metadata of a file or directory which is preserved in the archive.
+///
+/// # Fields
+///
+/// * `mtime` - The modification time (Unix timestamp).
+/// * `permissions` - The Unix permissions.
+/// * `uid` - The Unix user ID of the owner (only stored in tar archives).
+/// * `gid` - The Unix group ID of the owner (only stored in tar archives).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileMetadata {
+    pub mtime: Option<i64>,
+    pub permissions: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>
+}
+
+impl FileMetadata {
+    /// Reads the metadata of a file or directory on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file or directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FileMetadata)` - The metadata of the path.
+    /// * `Err(Error)` - If the metadata cannot be read.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let metadata = fs::metadata(path)?;
+        let mtime = metadata.modified().ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64);
+
+        #[cfg(unix)]
+        let (permissions, uid, gid) = {
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+            (
+                Some(metadata.permissions().mode() & 0o7777),
+                Some(metadata.uid()),
+                Some(metadata.gid())
+            )
+        };
+        #[cfg(not(unix))]
+        let (permissions, uid, gid) = (None, None, None);
+
+        Ok(Self { mtime, permissions, uid, gid })
+    }
+
+    /// Reads the metadata of a ZIP entry. The modification time is taken from
+    /// the extended timestamp field or, if missing, from the DOS timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The ZIP entry.
+    ///
+    /// # Returns
+    ///
+    /// * `FileMetadata` - The metadata of the entry.
+    pub fn from_zip_entry(entry: &ZipFile) -> Self {
+        let extended_mtime = entry.extra_data_fields().find_map(|field| {
+            match field {
+                ExtraField::ExtendedTimestamp(ts) => ts.mod_time(),
+                _ => None
+            }
+        });
+
+        let mtime = extended_mtime.map(i64::from).or_else(|| {
+            let dos_time = entry.last_modified()?;
+            match Local.with_ymd_and_hms(
+                dos_time.year() as i32, dos_time.month() as u32,
+                dos_time.day() as u32, dos_time.hour() as u32,
+                dos_time.minute() as u32, dos_time.second() as u32
+            ) {
+                LocalResult::Single(time) => Some(time.timestamp()),
+                _ => None
+            }
+        });
+
+        Self {
+            mtime,
+            permissions: entry.unix_mode().map(|mode| mode & 0o7777),
+            uid: None,
+            gid: None
+        }
+    }
+
+    /// Returns the file options for a ZIP entry with this metadata. The
+    /// modification time is stored in the DOS timestamp and in the extended
+    /// timestamp field.
+    ///
+    /// # Returns
+    ///
+    /// * `FullFileOptions` - The file options for the ZIP entry.
+    pub fn zip_options(&self) -> FullFileOptions<'static> {
+        let mut options = FullFileOptions::default()
+            .compression_method(CompressionMethod::Deflated);
+
+        if let Some(permissions) = self.permissions {
+            options = options.unix_permissions(permissions);
+        }
+
+        if let Some(mtime) = self.mtime {
+            if let LocalResult::Single(time) = Local.timestamp_opt(mtime, 0)
+            && let Ok(dos_time) = zip::DateTime::from_date_and_time(
+                time.year() as u16, time.month() as u8, time.day() as u8,
+                time.hour() as u8, time.minute() as u8, time.second() as u8
+            ) {
+                options = options.last_modified_time(dos_time);
+            }
+
+            // Flags (modification time present) + modification time
+            if let Ok(mtime) = u32::try_from(mtime) {
+                let mut data = vec![0b0000_0001];
+                data.extend_from_slice(&mtime.to_le_bytes());
+                let _ = options.add_extra_data(
+                    EXTENDED_TIMESTAMP_ID, data.into_boxed_slice(), false
+                );
+            }
+        }
+
+        options
+    }
+
+    /// Adds the write permission for the owner to an existing directory, so
+    /// its content can be replaced (e.g. a directory which was restored with
+    /// mode 0555). The stored mode is re-applied with `apply` afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the directory is writable (or isn't a directory).
+    /// * `Err(Error)` - If the permissions cannot be changed.
+    pub fn make_writable(path: &Path) -> Result<(), Error> {
+        #[cfg(unix)]
+        if let Ok(metadata) = fs::symlink_metadata(path)
+        && metadata.is_dir() {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = metadata.permissions().mode();
+            if mode & 0o200 == 0 {
+                fs::set_permissions(
+                    path, fs::Permissions::from_mode(mode | 0o200)
+                )?;
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = path;
+
+        Ok(())
+    }
+
+    /// Adds the write permission for the owner to a directory and all
+    /// directories below it, so the whole tree can be removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If all directories are writable.
+    /// * `Err(Error)` - If a directory cannot be read or changed.
+    pub fn make_tree_writable(path: &Path) -> Result<(), Error> {
+        Self::make_writable(path)?;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                Self::make_tree_writable(&entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens a file or directory for changing its modification time. Files
+    /// are opened for writing; read-only files (e.g. restored with mode
+    /// 0400) and directories can only be opened for reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file or directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(File)` - The opened file or directory.
+    /// * `Err(Error)` - If it cannot be opened.
+    fn open_for_times(path: &Path) -> Result<File, Error> {
+        if path.is_dir() {
+            return File::open(path);
+        }
+        match File::options().write(true).open(path) {
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                File::open(path)
+            },
+            result => result
+        }
+    }
+
+    /// Applies the metadata to a file or directory on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file or directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the metadata was applied.
+    /// * `Err(Error)` - If an error occurs.
+    pub fn apply(&self, path: &Path) -> Result<(), Error> {
+        if let Some(mtime) = self.mtime.and_then(|m| u64::try_from(m).ok()) {
+            let time: SystemTime = UNIX_EPOCH + Duration::from_secs(mtime);
+            Self::open_for_times(path)?.set_modified(time)?;
+        }
+
+        // Changing the owner requires root privileges, so it is only tried
+        #[cfg(unix)]
+        if self.uid.is_some() || self.gid.is_some() {
+            let _ = std::os::unix::fs::chown(path, self.uid, self.gid);
+        }
+
+        // Permissions are set last, they might remove the read permission
+        #[cfg(unix)]
+        if let Some(permissions) = self.permissions {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(permissions))?;
+        }
+
+        Ok(())
+    }
+}
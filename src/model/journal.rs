@@ -0,0 +1,181 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+
+/// The file in the backup directory of a config which holds the journal of
+/// the running extraction.
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+
+/// A planned operation of an extraction: an entry of the extracted archive
+/// is copied to its local path.
+///
+/// # Fields
+///
+/// * `from` - The path of the entry in the extracted archive.
+/// * `to` - The local path.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JournalStep {
+    pub from: PathBuf,
+    pub to: PathBuf
+}
+
+/// A line of the journal file.
+///
+/// # Variants
+///
+/// * `Begin` - The planned steps and the backup of the extraction (written
+///   before the first step).
+/// * `Done` - The step with this index was completed.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalRecord {
+    Begin { backup: Option<PathBuf>, steps: Vec<JournalStep> },
+    Done(usize)
+}
+
+/// The journal of an extraction. All steps are written before the first
+/// local file is changed and every completed step is marked immediately.
+/// The journal is removed when the extraction finished, so an existing
+/// journal means that the extraction was interrupted.
+///
+/// # Fields
+///
+/// * `path` - The path of the journal file.
+/// * `backup` - The backup directory of the extraction (`None` if backups
+///   are disabled).
+/// * `steps` - The planned steps.
+/// * `completed` - For every step whether it was completed.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    pub backup: Option<PathBuf>,
+    pub steps: Vec<JournalStep>,
+    pub completed: Vec<bool>
+}
+
+impl Journal {
+    /// Writes the journal of a new extraction.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The backup directory of the config.
+    /// * `backup` - The backup directory of the extraction (if any).
+    /// * `steps` - The planned steps.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Journal)` - The journal.
+    /// * `Err(Error)` - If the journal cannot be written.
+    pub fn begin(root: &Path, backup: Option<PathBuf>, steps: Vec<JournalStep>)
+    -> Result<Journal, Error> {
+        fs::create_dir_all(root)?;
+        let journal = Journal {
+            path: root.join(JOURNAL_FILE),
+            backup,
+            completed: vec![false; steps.len()],
+            steps
+        };
+
+        let mut file = File::create(&journal.path)?;
+        let record = JournalRecord::Begin {
+            backup: journal.backup.clone(),
+            steps: journal.steps.clone()
+        };
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        file.sync_all()?;
+        Ok(journal)
+    }
+
+    /// Reads the journal of an interrupted extraction.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The backup directory of the config.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Journal))` - The journal of the interrupted extraction.
+    /// * `Ok(None)` - If no extraction was interrupted.
+    /// * `Err(Error)` - If the journal cannot be read.
+    pub fn open(root: &Path) -> Result<Option<Journal>, Error> {
+        let path = root.join(JOURNAL_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let mut journal: Option<Journal> = None;
+        for line in BufReader::new(File::open(&path)?).lines() {
+            // The last line may be incomplete if the process was killed
+            let Ok(record) = serde_json::from_str(&line?) else {
+                continue;
+            };
+
+            match (record, journal.as_mut()) {
+                (JournalRecord::Begin { backup, steps }, None) => {
+                    journal = Some(Journal {
+                        path: path.clone(),
+                        backup,
+                        completed: vec![false; steps.len()],
+                        steps
+                    });
+                },
+                (JournalRecord::Done(index), Some(journal)) => {
+                    if let Some(completed) = journal.completed.get_mut(index) {
+                        *completed = true;
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        journal.map(Some).ok_or_else(|| Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid journal: {}", path.display())
+        ))
+    }
+
+    /// Marks a step as completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the step.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the step was marked.
+    /// * `Err(Error)` - If the journal cannot be written.
+    pub fn complete(&mut self, index: usize) -> Result<(), Error> {
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(
+            file, "{}", serde_json::to_string(&JournalRecord::Done(index))?
+        )?;
+        file.sync_all()?;
+        self.completed[index] = true;
+        Ok(())
+    }
+
+    /// Returns the steps which were not completed yet.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(usize, JournalStep)>` - The indices and the pending steps.
+    pub fn pending(&self) -> Vec<(usize, JournalStep)> {
+        self.steps.iter().cloned().enumerate()
+            .filter(|(index, _step)| !self.completed[*index])
+            .collect()
+    }
+
+    /// Removes the journal after the extraction finished (or was rolled
+    /// back).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the journal was removed.
+    /// * `Err(Error)` - If the journal cannot be removed.
+    pub fn finish(self) -> Result<(), Error> {
+        fs::remove_file(&self.path)
+    }
+}
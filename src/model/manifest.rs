@@ -0,0 +1,136 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+
+/// The path of the manifest inside the ZIP archive.
+pub const MANIFEST_PATH: &str = ".zipsync/manifest.json";
+
+
+/// The manifest which is embedded in the ZIP archive. It lists every entry
+/// together with the source path and the task that produced it.
+///
+/// # Fields
+///
+/// * `tool_version` - The version of ZipSync that created the archive.
+/// * `created_at` - The creation time of the archive (RFC 3339).
+/// * `hash_algorithm` - The hash algorithm of the entries ("sha256" or
+///   "blake3").
+/// * `entries` - The entries of the archive.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Manifest {
+    pub tool_version: String,
+    pub created_at: String,
+    pub hash_algorithm: String,
+    pub entries: Vec<ManifestEntry>
+}
+
+/// A single entry of the manifest.
+///
+/// # Fields
+///
+/// * `path` - The path of the entry in the ZIP archive.
+/// * `source` - The source path of the entry on disk.
+/// * `task` - The description of the copy task that produced the entry.
+/// * `size` - The size of the file in bytes.
+/// * `mtime` - The modification time of the file (Unix timestamp).
+/// * `permissions` - The Unix permissions of the file.
+/// * `hash` - The hash of the file content (hex encoded).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub source: String,
+    pub task: String,
+    pub size: u64,
+    pub mtime: Option<i64>,
+    pub permissions: Option<u32>,
+    pub hash: String
+}
+
+impl Manifest {
+    /// Creates a new, empty manifest for the current version of ZipSync.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash_algorithm` - The hash algorithm ("sha256" or "blake3"). An
+    ///   empty string selects "sha256".
+    ///
+    /// # Returns
+    ///
+    /// * `Manifest` - A new instance of the `Manifest` struct.
+    pub fn new(hash_algorithm: &str) -> Self {
+        let hash_algorithm = if hash_algorithm == "blake3" {
+            "blake3"
+        } else {
+            "sha256"
+        };
+
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Local::now().to_rfc3339(),
+            hash_algorithm: hash_algorithm.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the hash of the given data using the hash algorithm of the
+    /// manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to hash.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The hex encoded hash.
+    pub fn hash(&self, data: &[u8]) -> String {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    /// Returns a hasher for content which is read in chunks, using the hash
+    /// algorithm of the manifest.
+    ///
+    /// # Returns
+    ///
+    /// * `ContentHasher` - The hasher.
+    pub fn hasher(&self) -> ContentHasher {
+        if self.hash_algorithm == "blake3" {
+            ContentHasher::Blake3(Box::new(blake3::Hasher::new()))
+        } else {
+            ContentHasher::Sha256(Sha256::new())
+        }
+    }
+}
+
+
+/// Hashes content in chunks with the hash algorithm of a manifest.
+pub enum ContentHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl ContentHasher {
+    /// Adds the next chunk of the content.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The chunk.
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            ContentHasher::Sha256(hasher) => hasher.update(data),
+            ContentHasher::Blake3(hasher) => { hasher.update(data); },
+        }
+    }
+
+    /// Returns the hex encoded hash of the content.
+    pub fn finish(self) -> String {
+        let bytes = match self {
+            ContentHasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            ContentHasher::Blake3(hasher) => {
+                hasher.finalize().as_bytes().to_vec()
+            },
+        };
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
@@ -1,3 +1,15 @@
+pub mod archive_entry;
+pub mod backup;
+pub mod cli_args;
+pub mod compression;
 pub mod config;
+pub mod config_loader;
 pub mod copy_task;
+pub mod file_metadata;
+pub mod journal;
+pub mod manifest;
+pub mod password_source;
+pub mod path_mapping;
+pub mod retention;
+pub mod snapshot;
 pub mod sync_task;
\ No newline at end of file
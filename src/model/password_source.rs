@@ -0,0 +1,138 @@
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Seek};
+use std::process::Command;
+use serde::{Serialize, Deserialize};
+use zip::ZipArchive;
+
+
+/// The source of the password for encrypted archives. The password itself is
+/// never stored in the config.
+///
+/// # Variants
+///
+/// * `Env` - The name of an environment variable containing the password.
+/// * `File` - The path of a file containing the password.
+/// * `Command` - A shell command which prints the password to stdout
+///   (e.g. "pass show zipsync").
+/// * `Prompt` - The password is entered interactively.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordSource {
+    Env(String),
+    File(String),
+    Command(String),
+    Prompt,
+}
+
+impl PasswordSource {
+    /// Reads the password from the given source. If no source is given, the
+    /// password is entered interactively.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The password source from the config.
+    /// * `confirm` - Whether an interactively entered password has to be
+    ///   repeated (used when creating an archive).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The password.
+    /// * `Err(Error)` - If the password cannot be read or is empty.
+    pub fn read_password(source: Option<&PasswordSource>, confirm: bool)
+    -> Result<String, Error> {
+        let password = match source.unwrap_or(&PasswordSource::Prompt) {
+            PasswordSource::Env(name) => env::var(name).map_err(|_e| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Environment variable '{}' not set", name)
+                )
+            })?,
+            PasswordSource::File(path) => fs::read_to_string(path)?,
+            PasswordSource::Command(command) => Self::run_command(command)?,
+            PasswordSource::Prompt => Self::prompt(confirm)?
+        };
+
+        // Only the first line counts (e.g. "pass" prints additional lines)
+        let password = password.lines().next().unwrap_or_default().to_string();
+        if password.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Empty password"));
+        }
+
+        Ok(password)
+    }
+
+    /// Reads the password for an archive if it contains encrypted entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The ZIP archive.
+    /// * `source` - The password source from the config.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(String))` - The password, if the archive is encrypted.
+    /// * `Ok(None)` - If the archive isn't encrypted.
+    /// * `Err(Error)` - If the password cannot be read.
+    pub fn read_archive_password<R: Read + Seek>(
+        archive: &mut ZipArchive<R>,
+        source: Option<&PasswordSource>
+    ) -> Result<Option<String>, Error> {
+        for i in 0..archive.len() {
+            if archive.by_index_raw(i)?.encrypted() {
+                return Self::read_password(source, false).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Runs a shell command and returns its stdout.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The shell command.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The stdout of the command.
+    /// * `Err(Error)` - If the command cannot be run or fails.
+    fn run_command(command: &str) -> Result<String, Error> {
+        let output = if cfg!(windows) {
+            Command::new("cmd").args(["/C", command]).output()?
+        } else {
+            Command::new("sh").args(["-c", command]).output()?
+        };
+
+        if !output.status.success() {
+            return Err(Error::other(
+                format!("Password command failed: {}", output.status)
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Asks for the password on the terminal without echoing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `confirm` - Whether the password has to be repeated.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The password.
+    /// * `Err(Error)` - If the password cannot be read or doesn't match.
+    fn prompt(confirm: bool) -> Result<String, Error> {
+        let password = rpassword::prompt_password("Password: ")?;
+
+        if confirm && rpassword::prompt_password("Repeat password: ")?
+                      != password {
+            return Err(Error::new(
+                ErrorKind::InvalidInput, "Passwords don't match"
+            ));
+        }
+
+        Ok(password)
+    }
+}
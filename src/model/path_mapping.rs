@@ -0,0 +1,329 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::model::copy_task::CopyTask;
+
+
+/// The mapping between the source of a copy task and its path in the archive.
+/// Creating and extracting an archive both use this mapping, so every entry
+/// is extracted to the path it was read from.
+///
+/// The path in the archive is determined by the target of the task (leading
+/// and trailing slashes are ignored):
+/// * Directories are stored at the target, or under the name of the source
+///   directory if the target is empty.
+/// * Files are stored under their name if the target is empty, inside the
+///   target if it ends with a slash or has no extension, and at the target
+///   itself otherwise.
+///
+/// # Fields
+///
+/// * `archive_path` - The path of the source in the archive (without leading
+///   or trailing slashes).
+/// * `local_path` - The path of the source on disk.
+/// * `is_dir` - Whether the source is a directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathMapping {
+    pub archive_path: String,
+    pub local_path: PathBuf,
+    pub is_dir: bool
+}
+
+impl PathMapping {
+    /// Creates the mapping of a copy task for a source of the given kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The copy task.
+    /// * `is_dir` - Whether the source is a directory.
+    ///
+    /// # Returns
+    ///
+    /// * `PathMapping` - The mapping of the task.
+    pub fn new(task: &CopyTask, is_dir: bool) -> PathMapping {
+        let local_path = PathBuf::from(&task.source);
+        let name = local_path.file_name().map_or(
+            task.source.clone(),
+            |name| name.to_string_lossy().into_owned()
+        );
+        let target = task.target.trim_start_matches('/');
+
+        let archive_path = if target.is_empty() {
+            name
+        } else if is_dir {
+            target.to_string()
+        } else if target.ends_with('/')
+        || Path::new(target).extension().is_none() {
+            format!("{}/{}", target.trim_end_matches('/'), name)
+        } else {
+            target.to_string()
+        };
+
+        PathMapping {
+            archive_path: archive_path.trim_matches('/').to_string(),
+            local_path,
+            is_dir
+        }
+    }
+
+    /// Creates the mapping of a copy task for its source on disk. Symbolic
+    /// links are followed unless they are stored or skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The copy task.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathMapping>` - The mapping (`None` if the source doesn't
+    ///   exist).
+    pub fn from_source(task: &CopyTask) -> Option<PathMapping> {
+        let source = Path::new(&task.source);
+        let metadata = fs::symlink_metadata(source).ok()?;
+
+        let is_dir = if metadata.file_type().is_symlink()
+        && matches!(task.symlinks.as_str(), "store" | "skip") {
+            false
+        } else {
+            fs::metadata(source).ok()?.is_dir()
+        };
+        Some(Self::new(task, is_dir))
+    }
+
+    /// Creates the mapping of a copy task for its source on disk or, if the
+    /// source doesn't exist, for the source as it is written in the config
+    /// (a trailing slash marks a directory).
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The copy task.
+    ///
+    /// # Returns
+    ///
+    /// * `PathMapping` - The mapping of the task.
+    pub fn predict(task: &CopyTask) -> PathMapping {
+        Self::from_source(task)
+            .unwrap_or_else(|| Self::new(task, task.source.ends_with('/')))
+    }
+
+    /// Creates the mapping of a copy task for the content of an archive. The
+    /// kind of the source is taken from the archive; if the source exists on
+    /// disk, the archive has to contain it with the same kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The copy task.
+    /// * `kind_in_archive` - Returns for a path in the archive whether it is a
+    ///   directory (`None` if the archive doesn't contain it).
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathMapping>` - The mapping (`None` if the archive doesn't
+    ///   contain the source of the task).
+    pub fn from_archive<F>(task: &CopyTask, kind_in_archive: F)
+    -> Option<PathMapping>
+    where F: Fn(&str) -> Option<bool> {
+        let file = Self::new(task, false);
+        let directory = Self::new(task, true);
+        let has_file = kind_in_archive(&file.archive_path) == Some(false);
+        let has_directory = kind_in_archive(&directory.archive_path)
+            == Some(true);
+
+        match Self::from_source(task).map(|mapping| mapping.is_dir) {
+            Some(true) => has_directory.then_some(directory),
+            Some(false) => has_file.then_some(file),
+            None if has_file => Some(file),
+            None => has_directory.then_some(directory)
+        }
+    }
+
+    /// Returns the local path an entry of the archive is extracted to.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive_path` - The path of the entry in the archive.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathBuf>` - The local path (`None` if the entry doesn't
+    ///   belong to this mapping).
+    pub fn local_path_of(&self, archive_path: &str) -> Option<PathBuf> {
+        let archive_path = archive_path.trim_end_matches('/');
+        if archive_path == self.archive_path {
+            return Some(self.local_path.clone());
+        }
+        if !self.is_dir {
+            return None;
+        }
+
+        let relative = if self.archive_path.is_empty() {
+            Some(archive_path)
+        } else {
+            archive_path.strip_prefix(&format!("{}/", self.archive_path))
+        };
+        relative.map(|relative| self.local_path.join(relative))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use proptest::prelude::*;
+    use super::*;
+    use crate::controller::zip_creator_controller::ZipCreatorController;
+    use crate::controller::zip_reader_controller::ZipReaderController;
+    use crate::model::config::Config;
+
+    /// A directory the sources of the pure tests are in; it doesn't exist,
+    /// so the kind of a source is taken from the archive.
+    const MISSING_ROOT: &str = "/nonexistent-zipsync-test";
+
+    /// Creates a copy task with a source and a target.
+    fn task(source: &str, target: &str) -> CopyTask {
+        serde_json::from_value(serde_json::json!({
+            "source": source, "description": source, "target": target
+        })).unwrap()
+    }
+
+    /// Extracts an archive which contains exactly the entries of a mapping
+    /// (the source and, for directories, a child entry) and returns where
+    /// the entries end up.
+    fn extract(task: &CopyTask, created: &PathMapping, child: &str)
+    -> Option<(PathBuf, Option<PathBuf>)> {
+        let child_path = format!("{}/{}", created.archive_path, child);
+        let is_dir = created.is_dir;
+        let extracted = PathMapping::from_archive(task, |path| {
+            if path == created.archive_path {
+                Some(is_dir)
+            } else if is_dir && path == child_path {
+                Some(false)
+            } else {
+                None
+            }
+        })?;
+
+        let local = extracted.local_path_of(&created.archive_path)?;
+        let child = is_dir.then(|| extracted.local_path_of(&child_path))
+            .flatten();
+        Some((local, child))
+    }
+
+    #[test]
+    fn archive_paths() {
+        let cases = [
+            // (source, target, is_dir, archive path)
+            ("/data/docs", "", true, "docs"),
+            ("/data/docs", "backup/", true, "backup"),
+            ("/data/docs", "/backup/docs", true, "backup/docs"),
+            ("/data/docs", "docs.d", true, "docs.d"),
+            ("/data/a.txt", "", false, "a.txt"),
+            ("/data/a.txt", "files/", false, "files/a.txt"),
+            ("/data/a.txt", "files", false, "files/a.txt"),
+            ("/data/a.txt", "files/b.txt", false, "files/b.txt"),
+            ("/data/README", "/notes/", false, "notes/README")
+        ];
+
+        for (source, target, is_dir, expected) in cases {
+            let mapping = PathMapping::new(&task(source, target), is_dir);
+            assert_eq!(mapping.archive_path, expected, "{} -> {}",
+                       source, target);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(
+            name in "[a-z]{1,8}(\\.[a-z]{1,3})?",
+            target in prop_oneof![
+                Just(String::new()),
+                "/?[a-z]{1,8}(/[a-z]{1,8})?/",
+                "/?[a-z]{1,8}(/[a-z]{1,8})?",
+                "/?[a-z]{1,8}/[a-z]{1,8}\\.[a-z]{1,3}"
+            ],
+            is_dir in any::<bool>(),
+            child in "_[a-z0-9]{1,6}(/[a-z0-9]{1,6})?"
+        ) {
+            let source = format!("{}/{}", MISSING_ROOT, name);
+            let task = task(&source, &target);
+            let created = PathMapping::new(&task, is_dir);
+
+            let (local, local_child) = extract(&task, &created, &child)
+                .expect("the archive contains the source");
+            prop_assert_eq!(local, PathBuf::from(&source));
+            if is_dir {
+                prop_assert_eq!(
+                    local_child, Some(Path::new(&source).join(&child))
+                );
+            }
+        }
+    }
+
+    /// Returns the files below a directory with their content.
+    fn files(root: &Path) -> BTreeMap<PathBuf, String> {
+        let mut files = BTreeMap::new();
+        let mut directories = vec![root.to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(directory).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    directories.push(path);
+                } else {
+                    let content = fs::read_to_string(&path).unwrap();
+                    files.insert(path, content);
+                }
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn round_trip_through_archive() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let sources = root.join("sources");
+        for (path, content) in [
+            ("docs/a.txt", "a"),
+            ("docs/sub/b.txt", "b"),
+            ("photos/c.jpg", "c"),
+            ("config/d.json", "d"),
+            ("notes.txt", "notes"),
+            ("README", "readme"),
+            ("todo.txt", "todo")
+        ] {
+            let path = sources.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let tasks: Vec<_> = [
+            ("docs", ""),
+            ("photos", "media/"),
+            ("config", "settings/config.d"),
+            ("notes.txt", ""),
+            ("README", "files"),
+            ("todo.txt", "files/todo-list.txt")
+        ].iter().map(|(source, target)| {
+            task(&sources.join(source).to_string_lossy(), target)
+        }).collect();
+
+        let config = |direction: &str| -> Config {
+            serde_json::from_value(serde_json::json!({
+                "zip_path": root.join("archive.zip"),
+                "direction": direction,
+                "confirm_new": "no",
+                "confirm_overwrite": "no",
+                "confirm_delete": "no",
+                "backup": "no",
+                "backup_dir": root.join("backups"),
+                "copy_tasks": tasks
+            })).unwrap()
+        };
+
+        let expected = files(&sources);
+        assert!(ZipCreatorController::new(config("to_zip")).start());
+        fs::remove_dir_all(&sources).unwrap();
+        assert!(ZipReaderController::new(config("from_zip")).start());
+        assert_eq!(files(&sources), expected);
+    }
+}
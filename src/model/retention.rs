@@ -0,0 +1,202 @@
+use chrono::{Datelike, NaiveDateTime};
+use serde::{Serialize, Deserialize};
+
+
+/// The policy which decides which snapshots of an archive are kept. A
+/// snapshot is kept if any of the rules selects it; all other snapshots are
+/// deleted after a new one was created.
+///
+/// # Fields
+///
+/// * `keep_last` - The number of most recent snapshots which are kept.
+/// * `daily` - The number of days for which the newest snapshot is kept.
+/// * `weekly` - The number of weeks for which the newest snapshot is kept.
+/// * `monthly` - The number of months for which the newest snapshot is kept.
+///
+/// Days, weeks and months without a snapshot are not counted. A policy
+/// without any rule (which would delete all older snapshots) is rejected.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(try_from = "RetentionRules")]
+pub struct Retention {
+    #[serde(default)]
+    pub keep_last: Option<usize>,
+    #[serde(default)]
+    pub daily: Option<usize>,
+    #[serde(default)]
+    pub weekly: Option<usize>,
+    #[serde(default)]
+    pub monthly: Option<usize>
+}
+
+/// The rules of a retention policy as they are written in the config.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RetentionRules {
+    #[serde(default)]
+    keep_last: Option<usize>,
+    #[serde(default)]
+    daily: Option<usize>,
+    #[serde(default)]
+    weekly: Option<usize>,
+    #[serde(default)]
+    monthly: Option<usize>
+}
+
+impl TryFrom<RetentionRules> for Retention {
+    type Error = String;
+
+    fn try_from(rules: RetentionRules) -> Result<Self, Self::Error> {
+        let retention = Retention {
+            keep_last: rules.keep_last,
+            daily: rules.daily,
+            weekly: rules.weekly,
+            monthly: rules.monthly
+        };
+        let rules = [retention.keep_last, retention.daily, retention.weekly,
+                     retention.monthly];
+        if rules.iter().all(|rule| rule.unwrap_or(0) == 0) {
+            return Err(
+                "the retention policy keeps no snapshot (set keep_last, \
+                 daily, weekly or monthly)".to_string()
+            );
+        }
+        Ok(retention)
+    }
+}
+
+impl Retention {
+    /// Decides which snapshots are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `created` - The creation times of the snapshots (newest first).
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<bool>` - For every snapshot whether it is kept.
+    pub fn select(&self, created: &[NaiveDateTime]) -> Vec<bool> {
+        let mut keep = vec![false; created.len()];
+        for kept in keep.iter_mut().take(self.keep_last.unwrap_or(0)) {
+            *kept = true;
+        }
+
+        Self::keep_newest_per_period(
+            created, self.daily, |time| time.date(), &mut keep
+        );
+        Self::keep_newest_per_period(
+            created, self.weekly, |time| time.iso_week(), &mut keep
+        );
+        Self::keep_newest_per_period(
+            created, self.monthly, |time| (time.year(), time.month()),
+            &mut keep
+        );
+        keep
+    }
+
+    /// Keeps the newest snapshot of each of the most recent periods.
+    ///
+    /// # Arguments
+    ///
+    /// * `created` - The creation times of the snapshots (newest first).
+    /// * `periods` - The number of periods (none if `None`).
+    /// * `period_of` - Returns the period of a creation time.
+    /// * `keep` - For every snapshot whether it is kept.
+    fn keep_newest_per_period<P, F>(
+        created: &[NaiveDateTime],
+        periods: Option<usize>,
+        period_of: F,
+        keep: &mut [bool]
+    ) where P: PartialEq, F: Fn(&NaiveDateTime) -> P {
+        let Some(periods) = periods else {
+            return;
+        };
+
+        let mut last_period = None;
+        let mut kept = 0;
+        for (index, time) in created.iter().enumerate() {
+            if kept >= periods {
+                break;
+            }
+
+            let period = period_of(time);
+            if last_period.as_ref() != Some(&period) {
+                keep[index] = true;
+                kept += 1;
+                last_period = Some(period);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses creation times (newest first).
+    fn times(times: &[&str]) -> Vec<NaiveDateTime> {
+        times.iter()
+            .map(|time| {
+                NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn daily_rule_keeps_the_newest_snapshot_of_each_day() {
+        let created = times(&[
+            "2026-10-18 00:00", "2026-10-17 23:59", "2026-10-17 08:00",
+            "2026-10-15 12:00"
+        ]);
+        let retention = Retention { daily: Some(2), ..Retention::default() };
+        assert_eq!(retention.select(&created), [true, true, false, false]);
+
+        // Sunday and Monday are in different weeks
+        let created = times(&["2026-10-19 00:00", "2026-10-18 23:59"]);
+        let retention = Retention { weekly: Some(2), ..Retention::default() };
+        assert_eq!(retention.select(&created), [true, true]);
+    }
+
+    #[test]
+    fn snapshots_selected_by_any_rule_are_kept() {
+        let created = times(&[
+            "2026-10-18 12:00", "2026-10-18 08:00", "2026-10-17 12:00",
+            "2026-09-30 12:00", "2026-08-01 12:00"
+        ]);
+        let retention = Retention {
+            keep_last: Some(2),
+            daily: Some(2),
+            weekly: None,
+            monthly: Some(3)
+        };
+        assert_eq!(retention.select(&created),
+                   [true, true, true, true, true]);
+
+        let retention = Retention {
+            keep_last: Some(1),
+            daily: Some(1),
+            weekly: None,
+            monthly: Some(2)
+        };
+        assert_eq!(retention.select(&created),
+                   [true, false, false, true, false]);
+    }
+
+    #[test]
+    fn empty_and_unknown_rules_are_rejected() {
+        for rules in [
+            serde_json::json!({}),
+            serde_json::json!({ "keep_last": 0 }),
+            serde_json::json!({ "keep_last": 2, "dayly": 7 })
+        ] {
+            assert!(serde_json::from_value::<Retention>(rules).is_err());
+        }
+
+        let retention: Retention = serde_json::from_value(
+            serde_json::json!({ "daily": 7 })
+        ).unwrap();
+        assert_eq!(retention.daily, Some(7));
+        assert_eq!(Retention::default().select(&times(&["2026-10-18 12:00"])),
+                   [false]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+
+
+/// A timestamped generation of an archive. Snapshots are created when the
+/// `zip_path` is a template with `chrono` format specifiers in its file name
+/// (e.g. "backup-%Y-%m-%dT%H-%M.zip").
+///
+/// # Fields
+///
+/// * `path` - The path of the snapshot.
+/// * `created` - The time the snapshot was created (from its file name).
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub created: NaiveDateTime
+}
+
+impl Snapshot {
+    /// Checks if a `zip_path` is a snapshot template.
+    ///
+    /// # Arguments
+    ///
+    /// * `zip_path` - The `zip_path` of the config.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the path contains format specifiers.
+    pub fn is_template(zip_path: &str) -> bool {
+        zip_path.contains('%')
+    }
+
+    /// Returns the path of the snapshot which is created now.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The snapshot template.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The path of the new snapshot.
+    /// * `Err(Error)` - If the template is invalid.
+    pub fn new_path(template: &str) -> Result<String, Error> {
+        let (_directory, file_name) = Self::split_template(template)?;
+        if StrftimeItems::new(&file_name).any(|item| item == Item::Error) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid format in zip_path: {}", template)
+            ));
+        }
+
+        Ok(Local::now().format(template).to_string())
+    }
+
+    /// Returns all snapshots of a template, the newest first. Files whose
+    /// names don't match the template are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The snapshot template.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Snapshot>)` - The snapshots.
+    /// * `Err(Error)` - If the template is invalid or the directory cannot be
+    ///   read.
+    pub fn list(template: &str) -> Result<Vec<Snapshot>, Error> {
+        let (directory, file_name) = Self::split_template(template)?;
+        if !directory.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some(created) = Self::parse_time(&name, &file_name) {
+                snapshots.push(Snapshot { path: entry.path(), created });
+            }
+        }
+
+        snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.created));
+        Ok(snapshots)
+    }
+
+    /// Returns the timestamp of the snapshot as it is displayed and selected.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The timestamp (e.g. "2026-10-16T12:00:00").
+    pub fn timestamp(&self) -> String {
+        self.created.format("%Y-%m-%dT%H:%M:%S").to_string()
+    }
+
+    /// Checks if a snapshot matches a selector: the beginning of its
+    /// timestamp (a space or '-' may be used instead of 'T' and ':') or a
+    /// part of its file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The selector (e.g. "2026-10-16 12").
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the snapshot matches.
+    pub fn matches(&self, selector: &str) -> bool {
+        let normalize = |text: &str| text.replace([' ', 'T', ':'], "-");
+        normalize(&self.timestamp()).starts_with(&normalize(selector))
+            || self.path.file_name()
+                .is_some_and(|name| name.to_string_lossy().contains(selector))
+    }
+
+    /// Splits a template into its directory and its file name. Format
+    /// specifiers are only supported in the file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The snapshot template.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((PathBuf, String))` - The directory and the file name.
+    /// * `Err(Error)` - If the directory contains format specifiers.
+    fn split_template(template: &str) -> Result<(PathBuf, String), Error> {
+        let path = Path::new(template);
+        let directory = path.parent().unwrap_or(Path::new(""));
+        if Self::is_template(&directory.to_string_lossy()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Format specifiers are only supported in the file \
+                         name: {}", template)
+            ));
+        }
+
+        let directory = if directory.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            directory.to_path_buf()
+        };
+        let file_name = path.file_name().unwrap_or_default();
+        Ok((directory, file_name.to_string_lossy().to_string()))
+    }
+
+    /// Reads the creation time from the file name of a snapshot. Templates
+    /// without a time of day result in midnight.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name.
+    /// * `format` - The file name of the template.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<NaiveDateTime>` - The creation time (`None` if the name
+    ///   doesn't match the template).
+    fn parse_time(name: &str, format: &str) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(name, format).ok().or_else(|| {
+            NaiveDate::parse_from_str(name, format).ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+    }
+}